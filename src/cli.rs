@@ -0,0 +1,148 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use crate::{output::ImageFormat, scene::description::CameraOverrides};
+
+/// Path traces a scene and writes the result to an image file
+#[derive(Parser)]
+#[command(version)]
+pub struct Cli {
+    /// TOML scene description to render
+    #[arg(short, long, conflicts_with = "preset")]
+    pub scene: Option<PathBuf>,
+
+    /// Built-in scene to render when no scene file is given
+    #[arg(short, long, default_value = "final")]
+    pub preset: String,
+
+    /// Output image, the format follows the extension unless --format is given
+    #[arg(short, long, default_value = "out.png")]
+    pub output: PathBuf,
+
+    /// One of png, ppm, ppm-ascii, exr, exr-uncompressed or hdr
+    #[arg(short, long)]
+    pub format: Option<ImageFormat>,
+
+    /// Image width in pixels
+    #[arg(long, value_parser = parse_count)]
+    pub width: Option<usize>,
+
+    /// Image height in pixels, changes the aspect ratio when given with --width
+    #[arg(long, value_parser = parse_count)]
+    pub height: Option<usize>,
+
+    /// Samples per pixel
+    #[arg(long, value_parser = parse_count)]
+    pub spp: Option<usize>,
+
+    /// Maximum number of bounces per path
+    #[arg(long)]
+    pub max_depth: Option<usize>,
+
+    /// Worker threads, 0 uses every available core
+    #[arg(short = 'j', long)]
+    pub threads: Option<usize>,
+
+    /// Seed for sampling and for randomised presets
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Camera position as x,y,z
+    #[arg(long, value_parser = parse_point, allow_hyphen_values = true)]
+    pub lookfrom: Option<[f32; 3]>,
+
+    /// Point the camera looks at as x,y,z
+    #[arg(long, value_parser = parse_point, allow_hyphen_values = true)]
+    pub lookat: Option<[f32; 3]>,
+
+    /// Vertical field of view in degrees
+    #[arg(long, value_parser = parse_vfov)]
+    pub vfov: Option<f32>,
+
+    /// Defocus blur cone angle in degrees, 0 disables depth of field
+    #[arg(long, value_parser = parse_non_negative)]
+    pub defocus_angle: Option<f32>,
+
+    /// Distance from the camera to the plane of perfect focus
+    #[arg(long, value_parser = parse_positive)]
+    pub focus_dist: Option<f32>,
+
+    /// Trace wavelengths instead of RGB, so dispersive glass splits light into colours
+    #[arg(long)]
+    pub spectral: bool,
+
+    /// Print the size and depth of the scene's BVH before rendering
+    #[arg(long)]
+    pub stats: bool,
+}
+
+impl Cli {
+    pub fn camera_overrides(&self) -> CameraOverrides {
+        CameraOverrides {
+            image_width: self.width,
+            image_height: self.height,
+            samples_per_pixel: self.spp,
+            max_depth: self.max_depth,
+            threads: self.threads,
+            seed: self.seed,
+            lookfrom: self.lookfrom,
+            lookat: self.lookat,
+            vfov: self.vfov,
+            defocus_angle: self.defocus_angle,
+            focus_dist: self.focus_dist,
+            spectral: self.spectral,
+        }
+    }
+}
+
+fn parse_count(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("must be at least 1".to_string()),
+        Ok(n) => Ok(n),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse_float(s: &str) -> Result<f32, String> {
+    let x = s.parse::<f32>().map_err(|e| e.to_string())?;
+    if !x.is_finite() {
+        return Err("must be a finite number".to_string());
+    }
+    Ok(x)
+}
+
+fn parse_positive(s: &str) -> Result<f32, String> {
+    let x = parse_float(s)?;
+    if x <= 0.0 {
+        return Err("must be positive".to_string());
+    }
+    Ok(x)
+}
+
+fn parse_non_negative(s: &str) -> Result<f32, String> {
+    let x = parse_float(s)?;
+    if x < 0.0 {
+        return Err("cannot be negative".to_string());
+    }
+    Ok(x)
+}
+
+fn parse_vfov(s: &str) -> Result<f32, String> {
+    let x = parse_float(s)?;
+    if x <= 0.0 || x >= 180.0 {
+        return Err("must be between 0 and 180 degrees".to_string());
+    }
+    Ok(x)
+}
+
+fn parse_point(s: &str) -> Result<[f32; 3], String> {
+    let components = s
+        .split(',')
+        .map(|c| parse_float(c.trim()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    components
+        .try_into()
+        .map_err(|_| "expected three comma separated numbers, e.g. 13,2,3".to_string())
+}
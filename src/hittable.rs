@@ -1,29 +1,61 @@
+pub mod aabb;
+pub mod bvh;
+pub mod constant_medium;
+pub mod csg;
+pub mod grid_medium;
+pub mod instance;
 pub mod material;
+pub mod mesh;
+pub mod pdf;
+pub mod quad;
+pub mod sdf;
 pub mod sphere;
+pub mod texture;
+pub mod transform;
+pub mod triangle;
+pub mod voxel_grid;
 
-use std::rc::Rc;
+use std::sync::{Arc, LazyLock};
 
-use crate::{utils::interval::Interval, Point3, Ray, Vec3};
+use crate::{
+    utils::{interval::Interval, sampler::Sampler},
+    Point3, Ray, Vec3,
+};
 
-use self::material::{lambertian::Lambertian, Material};
+use self::{
+    aabb::Aabb,
+    bvh::BvhNode,
+    material::{lambertian::Lambertian, Material},
+};
 pub struct HitRecord {
     pub p: Point3,
     pub normal: Vec3,
     pub t: f32,
+    /// Surface coordinates of the hit, used for texture lookups
+    pub u: f32,
+    pub v: f32,
+    /// Direction in which `u` grows along the surface, dp/du, which anisotropic materials line
+    /// up with. Zero where a surface has none, such as the poles of a sphere
+    pub tangent: Vec3,
     pub front_face: bool,
-    pub mat: Rc<dyn Material>,
+    pub mat: Arc<dyn Material>,
 }
 
+// Shared so that creating a blank record for every bounce does not allocate
+static DEFAULT_MATERIAL: LazyLock<Arc<dyn Material>> =
+    LazyLock::new(|| Arc::new(Lambertian::default()));
+
 impl Default for HitRecord {
     fn default() -> Self {
-        let default_material: Rc<dyn Material> = Rc::new(Lambertian::default());
-
         Self {
             p: Point3::default(),
             normal: Vec3::default(),
             t: 0.0,
+            u: 0.0,
+            v: 0.0,
+            tangent: Vec3::default(),
             front_face: false,
-            mat: default_material,
+            mat: Arc::clone(&DEFAULT_MATERIAL),
         }
     }
 }
@@ -39,32 +71,91 @@ impl HitRecord {
     }
 }
 
-pub trait Hittable {
-    fn hit(&mut self, ray: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool;
+pub trait Hittable: Send + Sync {
+    fn hit(&self, ray: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool;
+
+    fn bounding_box(&self) -> Aabb;
+
+    /// Solid angle density of `random` producing `direction` from `origin`, zero for shapes
+    /// that cannot be sampled as lights
+    fn pdf_value(&self, _origin: &Point3, _direction: &Vec3) -> f32 {
+        0.0
+    }
+
+    /// Direction from `origin` towards a random point on the surface
+    fn random(&self, _origin: &Point3, _sampler: &mut Sampler) -> Vec3 {
+        Vec3::new(1.0, 0.0, 0.0)
+    }
+
+    /// Every point where `r` crosses the surface within `ray_t`, nearest first
+    ///
+    /// On a closed shape `front_face` tells whether the ray enters or leaves at each one, which
+    /// is what constructive solid geometry combines shapes by. Found by asking for the next hit
+    /// beyond the last one until there are none left.
+    fn crossings(&self, r: &Ray, ray_t: Interval) -> Vec<HitRecord> {
+        let mut crossings = Vec::new();
+        let mut t_min = ray_t.min;
+        loop {
+            let mut rec = HitRecord::default();
+            // Intervals exclude their ends, so the last crossing is not found again
+            if !self.hit(r, Interval::new(t_min, ray_t.max), &mut rec) {
+                break;
+            }
+            t_min = rec.t;
+            crossings.push(rec);
+        }
+        crossings
+    }
+
+    /// Where `r` first collides with a participating medium within `ray_t`, at a distance drawn
+    /// with `sampler`. Surfaces are hit instead and never collide
+    fn collide(
+        &self,
+        _r: &Ray,
+        _ray_t: Interval,
+        _rec: &mut HitRecord,
+        _sampler: &mut Sampler,
+    ) -> bool {
+        false
+    }
+
+    /// Fraction of light a participating medium lets through along `r` over `ray_t`, estimated
+    /// with `sampler` where it has no closed form. Surfaces block light by being hit instead and
+    /// let everything through here
+    fn transmittance(&self, _r: &Ray, _ray_t: Interval, _sampler: &mut Sampler) -> f32 {
+        1.0
+    }
 }
 
 pub struct HittableList {
     pub objects: Vec<Box<dyn Hittable>>,
+    bbox: Aabb,
 }
 
 impl HittableList {
     pub fn new() -> Self {
         HittableList {
             objects: Vec::new(),
+            bbox: Aabb::EMPTY,
         }
     }
 
     pub fn add(&mut self, object: Box<dyn Hittable>) {
+        self.bbox = Aabb::enclosing(&self.bbox, &object.bounding_box());
         self.objects.push(object);
     }
+
+    pub fn into_bvh(self) -> BvhNode {
+        BvhNode::new(self)
+    }
 }
 
 impl Hittable for HittableList {
-    fn hit(&mut self, ray: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+    fn hit(&self, ray: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
         let mut hit_anything = false;
         let mut closest_so_far = ray_t.max;
 
-        for object in &mut self.objects {
+        for object in &self.objects {
             if object.hit(ray, Interval::new(ray_t.min, closest_so_far), rec) {
                 hit_anything = true;
                 closest_so_far = rec.t;
@@ -73,4 +164,54 @@ impl Hittable for HittableList {
 
         hit_anything
     }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    // Every object is picked with equal probability
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f32 {
+        if self.objects.is_empty() {
+            return 0.0;
+        }
+
+        let sum: f32 = self
+            .objects
+            .iter()
+            .map(|object| object.pdf_value(origin, direction))
+            .sum();
+        sum / self.objects.len() as f32
+    }
+
+    fn collide(
+        &self,
+        r: &Ray,
+        ray_t: Interval,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) -> bool {
+        let mut collided = false;
+        let mut closest_so_far = ray_t.max;
+
+        for object in &self.objects {
+            if object.collide(r, Interval::new(ray_t.min, closest_so_far), rec, sampler) {
+                collided = true;
+                closest_so_far = rec.t;
+            }
+        }
+
+        collided
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: Interval, sampler: &mut Sampler) -> f32 {
+        self.objects
+            .iter()
+            .map(|object| object.transmittance(r, ray_t, sampler))
+            .product()
+    }
+
+    fn random(&self, origin: &Point3, sampler: &mut Sampler) -> Vec3 {
+        let index = (sampler.random_float() * self.objects.len() as f32) as usize;
+        self.objects[index.min(self.objects.len() - 1)].random(origin, sampler)
+    }
 }
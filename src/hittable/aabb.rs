@@ -0,0 +1,114 @@
+use crate::{utils::interval::Interval, Point3, Ray};
+
+/// Axis-aligned bounding box stored as one interval per axis
+#[derive(Clone, Copy, Debug)]
+pub struct Aabb {
+    pub x: Interval,
+    pub y: Interval,
+    pub z: Interval,
+}
+
+impl Aabb {
+    pub const EMPTY: Self = Self {
+        x: Interval::EMPTY,
+        y: Interval::EMPTY,
+        z: Interval::EMPTY,
+    };
+
+    pub fn new(x: Interval, y: Interval, z: Interval) -> Self {
+        Self { x, y, z }.pad_to_minimums()
+    }
+
+    /// Box spanning two opposite corners, given in any order
+    pub fn from_points(a: Point3, b: Point3) -> Self {
+        Self::new(
+            Interval::new(a.x().min(b.x()), a.x().max(b.x())),
+            Interval::new(a.y().min(b.y()), a.y().max(b.y())),
+            Interval::new(a.z().min(b.z()), a.z().max(b.z())),
+        )
+    }
+
+    pub fn enclosing(a: &Aabb, b: &Aabb) -> Self {
+        Self {
+            x: Interval::enclosing(&a.x, &b.x),
+            y: Interval::enclosing(&a.y, &b.y),
+            z: Interval::enclosing(&a.z, &b.z),
+        }
+    }
+
+    pub fn axis_interval(&self, n: usize) -> &Interval {
+        match n {
+            1 => &self.y,
+            2 => &self.z,
+            _ => &self.x,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.size() < 0.0 || self.y.size() < 0.0 || self.z.size() < 0.0
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        Point3::new(
+            0.5 * (self.x.min + self.x.max),
+            0.5 * (self.y.min + self.y.max),
+            0.5 * (self.z.min + self.z.max),
+        )
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        if self.is_empty() {
+            return 0.0;
+        }
+
+        let (dx, dy, dz) = (self.x.size(), self.y.size(), self.z.size());
+        2.0 * (dx * dy + dy * dz + dz * dx)
+    }
+
+    pub fn hit(&self, r: &Ray, ray_t: Interval) -> bool {
+        self.clip(r, ray_t).is_some()
+    }
+
+    /// Part of `ray_t` along `r` that lies inside the box
+    pub fn clip(&self, r: &Ray, mut ray_t: Interval) -> Option<Interval> {
+        let origin = r.origin();
+        let direction = r.direction();
+
+        for axis in 0..3 {
+            let ax = self.axis_interval(axis);
+            let adinv = 1.0 / direction[axis];
+
+            let t0 = (ax.min - origin[axis]) * adinv;
+            let t1 = (ax.max - origin[axis]) * adinv;
+            let (t0, t1) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+
+            ray_t.min = ray_t.min.max(t0);
+            ray_t.max = ray_t.max.min(t1);
+
+            // Slabs of flat boxes can round to a single t, which still counts as a hit
+            if ray_t.max < ray_t.min {
+                return None;
+            }
+        }
+
+        Some(ray_t)
+    }
+
+    // Keep flat primitives from producing zero-width slabs
+    fn pad_to_minimums(self) -> Self {
+        let delta = 0.0001;
+        let pad = |i: Interval| if i.size() < delta { i.expand(delta) } else { i };
+
+        Self {
+            x: pad(self.x),
+            y: pad(self.y),
+            z: pad(self.z),
+        }
+    }
+}
+
+impl Default for Aabb {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
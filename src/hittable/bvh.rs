@@ -0,0 +1,273 @@
+use crate::{utils::interval::Interval, Ray};
+
+use super::{aabb::Aabb, HitRecord, Hittable, HittableList};
+
+// Number of centroid buckets evaluated per axis when searching for a split
+const SAH_BINS: usize = 12;
+// Cost of visiting a node, relative to one primitive intersection test
+const TRAVERSAL_COST: f32 = 0.125;
+// Leaves may hold more than one primitive when splitting would cost more
+const MAX_LEAF_SIZE: usize = 4;
+
+/// Node and leaf counts of a built hierarchy
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BvhStats {
+    /// Total number of nodes, leaves included
+    pub nodes: usize,
+    pub leaves: usize,
+    pub primitives: usize,
+    pub max_depth: usize,
+    pub max_leaf_size: usize,
+}
+
+enum BvhContents {
+    Branch(Box<BvhNode>, Box<BvhNode>),
+    Leaf(HittableList),
+}
+
+/// Bounding volume hierarchy built with a binned surface area heuristic
+pub struct BvhNode {
+    bbox: Aabb,
+    contents: BvhContents,
+}
+
+struct Primitive {
+    bbox: Aabb,
+    centroid: [f32; 3],
+    object: Box<dyn Hittable>,
+}
+
+#[derive(Clone, Copy)]
+struct Bin {
+    bbox: Aabb,
+    count: usize,
+}
+
+impl BvhNode {
+    pub fn new(list: HittableList) -> Self {
+        let primitives = list
+            .objects
+            .into_iter()
+            .map(|object| {
+                let bbox = object.bounding_box();
+                let c = bbox.centroid();
+                Primitive {
+                    bbox,
+                    centroid: [c.x(), c.y(), c.z()],
+                    object,
+                }
+            })
+            .collect();
+
+        Self::build(primitives)
+    }
+
+    pub fn stats(&self) -> BvhStats {
+        let mut stats = BvhStats::default();
+        self.collect_stats(1, &mut stats);
+        stats
+    }
+
+    fn collect_stats(&self, depth: usize, stats: &mut BvhStats) {
+        stats.nodes += 1;
+        stats.max_depth = stats.max_depth.max(depth);
+
+        match &self.contents {
+            BvhContents::Branch(left, right) => {
+                left.collect_stats(depth + 1, stats);
+                right.collect_stats(depth + 1, stats);
+            }
+            BvhContents::Leaf(list) => {
+                stats.leaves += 1;
+                stats.primitives += list.objects.len();
+                stats.max_leaf_size = stats.max_leaf_size.max(list.objects.len());
+            }
+        }
+    }
+
+    fn leaf(primitives: Vec<Primitive>) -> Self {
+        let mut list = HittableList::new();
+        for primitive in primitives {
+            list.add(primitive.object);
+        }
+
+        Self {
+            bbox: list.bounding_box(),
+            contents: BvhContents::Leaf(list),
+        }
+    }
+
+    fn build(mut primitives: Vec<Primitive>) -> Self {
+        let n = primitives.len();
+        if n <= 1 {
+            return Self::leaf(primitives);
+        }
+
+        let bbox = primitives
+            .iter()
+            .fold(Aabb::EMPTY, |b, p| Aabb::enclosing(&b, &p.bbox));
+
+        let mut centroid_min = [f32::INFINITY; 3];
+        let mut centroid_max = [-f32::INFINITY; 3];
+        for p in &primitives {
+            for axis in 0..3 {
+                centroid_min[axis] = centroid_min[axis].min(p.centroid[axis]);
+                centroid_max[axis] = centroid_max[axis].max(p.centroid[axis]);
+            }
+        }
+
+        let bin_of = |centroid: &[f32; 3], axis: usize| {
+            let extent = centroid_max[axis] - centroid_min[axis];
+            let offset = (centroid[axis] - centroid_min[axis]) / extent;
+            ((offset * SAH_BINS as f32) as usize).min(SAH_BINS - 1)
+        };
+
+        // Cheapest (cost, axis, first bin of the right half) over all axes
+        let mut best: Option<(f32, usize, usize)> = None;
+        for axis in 0..3 {
+            if centroid_max[axis] - centroid_min[axis] <= 0.0 {
+                continue;
+            }
+
+            let mut bins = [Bin {
+                bbox: Aabb::EMPTY,
+                count: 0,
+            }; SAH_BINS];
+            for p in &primitives {
+                let bin = &mut bins[bin_of(&p.centroid, axis)];
+                bin.bbox = Aabb::enclosing(&bin.bbox, &p.bbox);
+                bin.count += 1;
+            }
+
+            for split in 1..SAH_BINS {
+                let (left, right) = bins.split_at(split);
+                let (left_box, left_count) = Self::merge_bins(left);
+                let (right_box, right_count) = Self::merge_bins(right);
+                if left_count == 0 || right_count == 0 {
+                    continue;
+                }
+
+                let cost = TRAVERSAL_COST * bbox.surface_area()
+                    + left_box.surface_area() * left_count as f32
+                    + right_box.surface_area() * right_count as f32;
+                if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                    best = Some((cost, axis, split));
+                }
+            }
+        }
+
+        let leaf_cost = n as f32 * bbox.surface_area();
+        let (left, right): (Vec<_>, Vec<_>) = match best {
+            Some((cost, _, _)) if n <= MAX_LEAF_SIZE && cost >= leaf_cost => {
+                return Self::leaf(primitives);
+            }
+            Some((_, axis, split)) => primitives
+                .into_iter()
+                .partition(|p| bin_of(&p.centroid, axis) < split),
+            // Every centroid coincides, so no split can separate them spatially
+            None if n <= MAX_LEAF_SIZE => return Self::leaf(primitives),
+            None => {
+                let right = primitives.split_off(n / 2);
+                (primitives, right)
+            }
+        };
+
+        Self {
+            bbox,
+            contents: BvhContents::Branch(
+                Box::new(Self::build(left)),
+                Box::new(Self::build(right)),
+            ),
+        }
+    }
+
+    fn merge_bins(bins: &[Bin]) -> (Aabb, usize) {
+        bins.iter().fold((Aabb::EMPTY, 0), |(bbox, count), bin| {
+            (Aabb::enclosing(&bbox, &bin.bbox), count + bin.count)
+        })
+    }
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        if !self.bbox.hit(r, ray_t) {
+            return false;
+        }
+
+        match &self.contents {
+            BvhContents::Branch(left, right) => {
+                let hit_left = left.hit(r, ray_t, rec);
+                let right_max = if hit_left { rec.t } else { ray_t.max };
+                let hit_right = right.hit(r, Interval::new(ray_t.min, right_max), rec);
+
+                hit_left || hit_right
+            }
+            BvhContents::Leaf(list) => list.hit(r, ray_t, rec),
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        hittable::{material::lambertian::Lambertian, sphere::Sphere},
+        Colour, Point3, Vec3,
+    };
+
+    use super::*;
+
+    /// Scattered, partly overlapping spheres of different sizes
+    fn spheres() -> HittableList {
+        let mat = Arc::new(Lambertian::new(Colour::new(0.5, 0.5, 0.5)));
+        let mut list = HittableList::new();
+        for i in 0..200 {
+            let f = i as f32;
+            let center = Point3::new(
+                8.0 * (f * 1.3).sin(),
+                8.0 * (f * 0.7).cos(),
+                8.0 * (f * 2.9).sin(),
+            );
+            list.add(Box::new(Sphere::new(
+                center,
+                0.2 + 0.5 * (f * 0.37).sin().abs(),
+                mat.clone(),
+            )));
+        }
+        list
+    }
+
+    #[test]
+    fn hits_match_brute_force_list() {
+        let list = spheres();
+        let bvh = spheres().into_bvh();
+
+        let mut hits = 0;
+        for i in 0..2000 {
+            let f = i as f32;
+            let origin = Point3::new(12.0 * (f * 0.11).sin(), 12.0 * (f * 0.23).cos(), 15.0);
+            let target = Point3::new(6.0 * (f * 0.53).cos(), 6.0 * (f * 0.19).sin(), 0.0);
+            let r = Ray::new(origin, target - origin, 0.0);
+            let ray_t = Interval::new(0.001, f32::INFINITY);
+
+            let mut expected = HitRecord::default();
+            let mut actual = HitRecord::default();
+            let hit = list.hit(&r, ray_t, &mut expected);
+            assert_eq!(hit, bvh.hit(&r, ray_t, &mut actual), "ray {i}");
+            if hit {
+                hits += 1;
+                assert_eq!(expected.t, actual.t, "ray {i}");
+                assert_eq!(expected.front_face, actual.front_face, "ray {i}");
+                let d: Vec3 = expected.p - actual.p;
+                assert_eq!(d.length(), 0.0, "ray {i}");
+            }
+        }
+        // Most rays aim into the cloud, so both outcomes are exercised
+        assert!(hits > 200 && hits < 2000, "{hits} hits");
+    }
+}
@@ -0,0 +1,110 @@
+use std::sync::Arc;
+
+use crate::{
+    utils::{interval::Interval, sampler::Sampler},
+    Hittable, Ray, Vec3,
+};
+
+use super::{aabb::Aabb, material::Material, HitRecord};
+
+/// Participating medium of uniform density filling a closed, convex `boundary`, such as fog or
+/// smoke
+///
+/// A ray passing through scatters at an exponentially distributed distance and carries on
+/// otherwise, so the chance of crossing untouched is exactly the transmittance of the medium.
+/// `phase_function` decides where scattered light goes, typically `Isotropic` or
+/// `HenyeyGreenstein`.
+pub struct ConstantMedium {
+    boundary: Box<dyn Hittable>,
+    neg_inv_density: f32,
+    phase_function: Arc<dyn Material>,
+}
+
+impl ConstantMedium {
+    pub fn new(
+        boundary: Box<dyn Hittable>,
+        density: f32,
+        phase_function: Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            boundary,
+            neg_inv_density: -1.0 / density,
+            phase_function,
+        }
+    }
+}
+
+impl ConstantMedium {
+    /// Part of `ray_t` along `r` inside the boundary
+    fn span(&self, r: &Ray, ray_t: Interval) -> Option<Interval> {
+        // Both crossings of the whole line, the entry lies behind the origin when the ray
+        // starts inside the medium
+        let mut entry = HitRecord::default();
+        let mut exit = HitRecord::default();
+        if !self.boundary.hit(r, Interval::UNIVERSE, &mut entry) {
+            return None;
+        }
+        if !self
+            .boundary
+            .hit(r, Interval::new(entry.t + 0.0001, f32::INFINITY), &mut exit)
+        {
+            return None;
+        }
+
+        let span = Interval::new(entry.t.max(ray_t.min).max(0.0), exit.t.min(ray_t.max));
+        (span.size() > 0.0).then_some(span)
+    }
+}
+
+impl Hittable for ConstantMedium {
+    // Rays pass through media, collisions come from `collide`
+    fn hit(&self, _r: &Ray, _ray_t: Interval, _rec: &mut HitRecord) -> bool {
+        false
+    }
+
+    fn collide(
+        &self,
+        r: &Ray,
+        ray_t: Interval,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) -> bool {
+        let Some(Interval {
+            min: t_entry,
+            max: t_exit,
+        }) = self.span(r, ray_t)
+        else {
+            return false;
+        };
+
+        let ray_length = r.direction().length();
+        let distance_inside_boundary = (t_exit - t_entry) * ray_length;
+        let hit_distance = self.neg_inv_density * (1.0 - sampler.random_float()).ln();
+        if hit_distance > distance_inside_boundary {
+            return false;
+        }
+
+        rec.t = t_entry + hit_distance / ray_length;
+        rec.p = r.at(rec.t);
+        // Arbitrary, phase functions do not look at the normal
+        rec.normal = Vec3::new(1.0, 0.0, 0.0);
+        rec.front_face = true;
+        rec.u = 0.0;
+        rec.v = 0.0;
+        rec.mat = Arc::clone(&self.phase_function);
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+
+    // Beer-Lambert, no need to estimate anything
+    fn transmittance(&self, r: &Ray, ray_t: Interval, _sampler: &mut Sampler) -> f32 {
+        match self.span(r, ray_t) {
+            Some(span) => (span.size() * r.direction().length() / self.neg_inv_density).exp(),
+            None => 1.0,
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use serde::Deserialize;
+
+use crate::{utils::interval::Interval, Hittable, Ray};
+
+use super::{aabb::Aabb, HitRecord};
+
+/// How the insides of two shapes combine
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    /// Inside either shape
+    Union,
+    /// Inside both shapes
+    Intersection,
+    /// Inside the first shape but not the second
+    Difference,
+}
+
+impl Operation {
+    fn contains(&self, in_a: bool, in_b: bool) -> bool {
+        match self {
+            Self::Union => in_a || in_b,
+            Self::Intersection => in_a && in_b,
+            Self::Difference => in_a && !in_b,
+        }
+    }
+}
+
+/// Solid made by combining two closed shapes, such as a sphere with a box cut out of it
+///
+/// Both shapes report every point where the ray crosses them. Walking those in order while
+/// tracking whether the ray is inside each shape finds where it enters or leaves the
+/// combination. Every surface keeps its own material, so a cut shows the material of the shape
+/// that made it.
+pub struct Csg {
+    operation: Operation,
+    a: Box<dyn Hittable>,
+    b: Box<dyn Hittable>,
+    bbox: Aabb,
+}
+
+impl Csg {
+    /// `a` and `b` must be closed, so rays alternate between entering and leaving them
+    pub fn new(operation: Operation, a: Box<dyn Hittable>, b: Box<dyn Hittable>) -> Self {
+        let (box_a, box_b) = (a.bounding_box(), b.bounding_box());
+        let bbox = match operation {
+            Operation::Union => Aabb::enclosing(&box_a, &box_b),
+            Operation::Intersection => Aabb {
+                x: overlap(&box_a.x, &box_b.x),
+                y: overlap(&box_a.y, &box_b.y),
+                z: overlap(&box_a.z, &box_b.z),
+            },
+            Operation::Difference => box_a,
+        };
+
+        Self {
+            operation,
+            a,
+            b,
+            bbox,
+        }
+    }
+
+    /// Crossings of the combined surface within `ray_t`, stopping after the first when
+    /// `first_only` is set
+    fn boundary(&self, r: &Ray, ray_t: Interval, first_only: bool) -> Vec<HitRecord> {
+        // Crossings past the end of the interval are still needed to tell whether the ray
+        // starts inside a shape it only leaves later
+        let everywhere = Interval::new(ray_t.min, f32::INFINITY);
+        let a = self.a.crossings(r, everywhere);
+        let b = self.b.crossings(r, everywhere);
+
+        // A ray that first leaves a shape started inside it
+        let mut in_a = a.first().is_some_and(|rec| !rec.front_face);
+        let mut in_b = b.first().is_some_and(|rec| !rec.front_face);
+        let mut inside = self.operation.contains(in_a, in_b);
+
+        let (mut a, mut b) = (a.into_iter().peekable(), b.into_iter().peekable());
+        let mut boundary = Vec::new();
+        loop {
+            let from_a = match (a.peek(), b.peek()) {
+                (Some(next_a), Some(next_b)) => next_a.t <= next_b.t,
+                (next_a, _) => next_a.is_some(),
+            };
+            let Some(mut rec) = (if from_a { a.next() } else { b.next() }) else {
+                break;
+            };
+            if rec.t >= ray_t.max {
+                break;
+            }
+
+            if from_a {
+                in_a = rec.front_face;
+            } else {
+                in_b = rec.front_face;
+            }
+            let now_inside = self.operation.contains(in_a, in_b);
+            if now_inside != inside {
+                inside = now_inside;
+                // The normal already faces the ray, but a cut turns the surface of `b` inside
+                // out, so whether this is a front face follows the combination instead
+                rec.front_face = now_inside;
+                boundary.push(rec);
+                if first_only {
+                    break;
+                }
+            }
+        }
+
+        boundary
+    }
+}
+
+impl Hittable for Csg {
+    fn hit(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        if !self.bbox.hit(r, ray_t) {
+            return false;
+        }
+        match self.boundary(r, ray_t, true).pop() {
+            Some(first) => {
+                *rec = first;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn crossings(&self, r: &Ray, ray_t: Interval) -> Vec<HitRecord> {
+        self.boundary(r, ray_t, false)
+    }
+}
+
+fn overlap(a: &Interval, b: &Interval) -> Interval {
+    Interval::new(a.min.max(b.min), a.max.min(b.max))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        hittable::{
+            material::lambertian::Lambertian,
+            quad::make_box,
+            sdf::{Sdf, SdfNode},
+            sphere::Sphere,
+        },
+        Colour, Point3, Vec3,
+    };
+
+    use super::*;
+
+    fn box_minus(b: Box<dyn Hittable>) -> Csg {
+        let mat = Arc::new(Lambertian::new(Colour::new(0.5, 0.5, 0.5)));
+        let cube = make_box(
+            Point3::new(-1.0, -1.0, -1.0),
+            Point3::new(1.0, 1.0, 1.0),
+            mat,
+        );
+        Csg::new(Operation::Difference, Box::new(cube), b)
+    }
+
+    /// Where the ray crosses the solid and whether it enters there
+    fn crossings(csg: &Csg, r: &Ray) -> Vec<(f32, bool)> {
+        csg.crossings(r, Interval::new(0.001, f32::INFINITY))
+            .iter()
+            .map(|rec| (rec.t, rec.front_face))
+            .collect()
+    }
+
+    #[test]
+    fn sdf_operand_matches_analytic_operand() {
+        let mat = Arc::new(Lambertian::new(Colour::new(0.9, 0.1, 0.1)));
+        let analytic = box_minus(Box::new(Sphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            0.5,
+            mat.clone(),
+        )));
+        let sdf = box_minus(Box::new(Sdf::new(SdfNode::Sphere { radius: 0.5 }, mat)));
+
+        for r in [
+            Ray::new(Point3::new(-3.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0),
+            Ray::new(Point3::new(-3.0, 0.2, 0.1), Vec3::new(2.0, 0.0, 0.0), 0.0),
+            Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), 0.0),
+        ] {
+            let expected = crossings(&analytic, &r);
+            let found = crossings(&sdf, &r);
+            assert_eq!(expected.len(), found.len(), "{expected:?} vs {found:?}");
+            for ((t0, front0), (t1, front1)) in expected.iter().zip(&found) {
+                assert!((t0 - t1).abs() < 1e-3, "{expected:?} vs {found:?}");
+                assert_eq!(front0, front1);
+            }
+        }
+
+        // Through the hole, the ray leaves the box, enters the cut and leaves it again
+        let r = Ray::new(Point3::new(-3.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        assert_eq!(crossings(&sdf, &r).len(), 4);
+    }
+}
@@ -0,0 +1,250 @@
+use std::sync::Arc;
+
+use crate::{
+    utils::{interval::Interval, sampler::Sampler, spectrum::blackbody},
+    Colour, HitRecord, Hittable, Point3, Ray, Vec3,
+};
+
+use super::{
+    aabb::Aabb,
+    material::{Material, ScatterRecord},
+    voxel_grid::VoxelGrid,
+};
+
+/// Light given off by the absorbing part of a `GridMedium`
+///
+/// Both kinds give the radiance `Le` of the emitting matter, in the same units as the `emit`
+/// of a diffuse light. The medium adds σa·Le per unit length, so a thick enough cloud that
+/// absorbs everything glows as brightly as a light surface of that radiance, and one that only
+/// scatters gives off nothing.
+pub enum VolumeEmission {
+    None,
+    Uniform(Colour),
+    /// Black body colour of the temperature in kelvin sampled from the grid, whose spectrum
+    /// peaks at 1, times `intensity`
+    Blackbody {
+        temperature: VoxelGrid,
+        intensity: f32,
+    },
+}
+
+// Black body colours are tabulated up to the hottest voxel rather than integrated per collision
+const BLACKBODY_TABLE_SIZE: usize = 256;
+
+/// Heterogeneous participating medium such as smoke, clouds or fire, with its density read from
+/// a voxel grid
+///
+/// Free paths are sampled with delta tracking against the densest voxel: collisions are
+/// proposed as if the whole grid were that dense and accepted in proportion to the actual
+/// density, so the surviving rays see the true transmittance without any bias. Shadow rays use
+/// ratio tracking instead, which multiplies the fraction of every proposed collision that is
+/// empty space and so returns a smooth transmittance rather than all or nothing.
+pub struct GridMedium {
+    density: VoxelGrid,
+    density_scale: f32,
+    majorant: f32,
+    material: Arc<dyn Material>,
+}
+
+impl GridMedium {
+    /// `density_scale` turns grid values into extinction per unit length, `phase_function` is
+    /// typically `Isotropic` or `HenyeyGreenstein`
+    pub fn new(
+        density: VoxelGrid,
+        density_scale: f32,
+        phase_function: Arc<dyn Material>,
+        emission: VolumeEmission,
+    ) -> Self {
+        let (hottest, blackbody_table) = match &emission {
+            VolumeEmission::Blackbody { temperature, .. } => {
+                let hottest = temperature.max_value();
+                let table = (0..BLACKBODY_TABLE_SIZE)
+                    .map(|i| blackbody(hottest * i as f32 / (BLACKBODY_TABLE_SIZE - 1) as f32))
+                    .collect();
+                (hottest, table)
+            }
+            _ => (0.0, Vec::new()),
+        };
+
+        Self {
+            majorant: density.max_value() * density_scale,
+            density,
+            density_scale,
+            material: Arc::new(GridMaterial {
+                phase_function,
+                emission,
+                hottest,
+                blackbody_table,
+            }),
+        }
+    }
+
+    fn extinction(&self, p: &Point3) -> f32 {
+        self.density.lookup(p) * self.density_scale
+    }
+
+    // Distance along `r` in units of t to the next collision proposed by the majorant
+    fn step(&self, ray_length: f32, sampler: &mut Sampler) -> f32 {
+        -(1.0 - sampler.random_float()).ln() / (self.majorant * ray_length)
+    }
+}
+
+impl Hittable for GridMedium {
+    // Rays pass through media, collisions come from `collide`
+    fn hit(&self, _r: &Ray, _ray_t: Interval, _rec: &mut HitRecord) -> bool {
+        false
+    }
+
+    fn collide(
+        &self,
+        r: &Ray,
+        ray_t: Interval,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) -> bool {
+        if self.majorant <= 0.0 {
+            return false;
+        }
+        let Some(span) = self.density.bounding_box().clip(r, ray_t) else {
+            return false;
+        };
+
+        let ray_length = r.direction().length();
+        let mut t = span.min;
+        loop {
+            t += self.step(ray_length, sampler);
+            if t >= span.max {
+                return false;
+            }
+
+            // Anything short of a real collision is a null collision and the ray carries on
+            let p = r.at(t);
+            if sampler.random_float() * self.majorant < self.extinction(&p) {
+                rec.t = t;
+                rec.p = p;
+                // Arbitrary, phase functions do not look at the normal
+                rec.normal = Vec3::new(1.0, 0.0, 0.0);
+                rec.front_face = true;
+                rec.u = 0.0;
+                rec.v = 0.0;
+                rec.mat = Arc::clone(&self.material);
+                return true;
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.density.bounding_box()
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: Interval, sampler: &mut Sampler) -> f32 {
+        if self.majorant <= 0.0 {
+            return 1.0;
+        }
+        let Some(span) = self.density.bounding_box().clip(r, ray_t) else {
+            return 1.0;
+        };
+
+        let ray_length = r.direction().length();
+        let mut transmittance = 1.0;
+        let mut t = span.min;
+        loop {
+            t += self.step(ray_length, sampler);
+            if t >= span.max {
+                return transmittance;
+            }
+            transmittance *= 1.0 - self.extinction(&r.at(t)) / self.majorant;
+
+            // Russian roulette keeps thick volumes from tracking long after nothing is left
+            if transmittance < 0.1 {
+                if sampler.random_float() < 0.5 {
+                    return 0.0;
+                }
+                transmittance *= 2.0;
+            }
+        }
+    }
+}
+
+/// Phase function of a grid medium plus whatever the medium emits at the collision
+struct GridMaterial {
+    phase_function: Arc<dyn Material>,
+    emission: VolumeEmission,
+    /// Temperature at the last entry of `blackbody_table`, whose entries are evenly spaced
+    /// from zero
+    hottest: f32,
+    blackbody_table: Vec<Colour>,
+}
+
+impl Material for GridMaterial {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut Sampler) -> Option<ScatterRecord> {
+        self.phase_function.scatter(r_in, rec, sampler)
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> Colour {
+        self.phase_function.eval(r_in, rec, direction)
+    }
+
+    // Delta tracking stops at collisions in proportion to σt, of which only the absorbed share
+    // σa / σt emits
+    fn emitted(&self, u: f32, v: f32, p: &Point3) -> Colour {
+        let albedo = self.phase_function.albedo(u, v, p);
+        let absorbed = Colour::new(
+            (1.0 - albedo.x()).clamp(0.0, 1.0),
+            (1.0 - albedo.y()).clamp(0.0, 1.0),
+            (1.0 - albedo.z()).clamp(0.0, 1.0),
+        );
+        absorbed * self.radiance(p)
+    }
+}
+
+impl GridMaterial {
+    /// Radiance `Le` of the emitting matter at `p`
+    fn radiance(&self, p: &Point3) -> Colour {
+        match &self.emission {
+            VolumeEmission::None => Colour::default(),
+            VolumeEmission::Uniform(colour) => *colour,
+            VolumeEmission::Blackbody {
+                temperature,
+                intensity,
+            } => {
+                if self.hottest <= 0.0 {
+                    return Colour::default();
+                }
+                let x = (temperature.lookup(p) / self.hottest).clamp(0.0, 1.0)
+                    * (BLACKBODY_TABLE_SIZE - 1) as f32;
+                let i = (x as usize).min(BLACKBODY_TABLE_SIZE - 2);
+                let t = x - i as f32;
+                let colour =
+                    self.blackbody_table[i].scale(1.0 - t) + self.blackbody_table[i + 1].scale(t);
+                colour.scale(*intensity)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hittable::{material::isotropic::Isotropic, texture::solid_colour::SolidColour};
+
+    use super::*;
+
+    fn glowing(albedo: Colour) -> GridMaterial {
+        GridMaterial {
+            phase_function: Arc::new(Isotropic::new(Arc::new(SolidColour::new(albedo)))),
+            emission: VolumeEmission::Uniform(Colour::new(4.0, 2.0, 8.0)),
+            hottest: 0.0,
+            blackbody_table: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn only_the_absorbed_share_emits() {
+        let p = Point3::default();
+        let black = glowing(Colour::default()).emitted(0.0, 0.0, &p);
+        assert_eq!((black.x(), black.y(), black.z()), (4.0, 2.0, 8.0));
+
+        let grey = glowing(Colour::new(0.75, 0.5, 1.0)).emitted(0.0, 0.0, &p);
+        assert_eq!((grey.x(), grey.y(), grey.z()), (1.0, 1.0, 0.0));
+    }
+}
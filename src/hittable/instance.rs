@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use crate::{
+    utils::{interval::Interval, matrix::Mat4, sampler::Sampler},
+    Hittable, Point3, Ray, Vec3,
+};
+
+use super::{aabb::Aabb, material::Material, transform::Transform, HitRecord};
+
+/// One placement of shared geometry, optionally painted with its own material
+///
+/// The geometry, usually a `BvhNode` over its primitives, is built once and only referenced
+/// here, so a copy costs a transform and a bounding box however detailed the geometry is. A
+/// scene BVH over instances then forms the top level of a two-level hierarchy, each instance
+/// handing rays down to the bottom-level hierarchy of its geometry.
+pub struct Instance {
+    transform: Transform,
+    material: Option<Arc<dyn Material>>,
+}
+
+impl Instance {
+    /// `material` replaces the materials of every primitive in `geometry` when given
+    pub fn new(
+        geometry: Arc<dyn Hittable>,
+        to_world: Mat4,
+        material: Option<Arc<dyn Material>>,
+    ) -> Self {
+        Self {
+            transform: Transform::new(geometry, to_world),
+            material,
+        }
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        if !self.transform.hit(r, ray_t, rec) {
+            return false;
+        }
+        if let Some(material) = &self.material {
+            rec.mat = Arc::clone(material);
+        }
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.transform.bounding_box()
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f32 {
+        self.transform.pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Point3, sampler: &mut Sampler) -> Vec3 {
+        self.transform.random(origin, sampler)
+    }
+
+    fn collide(
+        &self,
+        r: &Ray,
+        ray_t: Interval,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) -> bool {
+        self.transform.collide(r, ray_t, rec, sampler)
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: Interval, sampler: &mut Sampler) -> f32 {
+        self.transform.transmittance(r, ray_t, sampler)
+    }
+}
@@ -1,17 +1,61 @@
+pub mod conductor;
 pub mod dielectric;
+pub mod diffuse_light;
+pub mod henyey_greenstein;
+pub mod isotropic;
 pub mod lambertian;
 pub mod metal;
+pub mod microfacet;
+pub mod principled;
+pub mod rough_dielectric;
+pub mod thin_film;
 
-use crate::{scene::ray::Ray, utils::colour::Colour};
+use crate::{
+    scene::ray::Ray,
+    utils::{colour::Colour, sampler::Sampler},
+    Point3, Vec3,
+};
 
-use super::HitRecord;
+use super::{pdf::Pdf, HitRecord};
 
-pub trait Material {
-    fn scatter(
-        &self,
-        r_in: &Ray,
-        rec: &HitRecord,
-        attenuation: &mut Colour,
-        scattered: &mut Ray,
-    ) -> bool;
+/// How a ray continues after hitting a surface
+pub enum ScatterRecord {
+    /// A single outgoing ray picked by the material, such as a mirror reflection or a refraction,
+    /// that light sampling cannot reach
+    Specular { attenuation: Colour, ray: Ray },
+    /// Directions drawn from `pdf` and weighted by `Material::eval`
+    Sampled { pdf: Box<dyn Pdf> },
+}
+
+pub trait Material: Send + Sync {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut Sampler) -> Option<ScatterRecord>;
+
+    /// BSDF times the cosine at the surface for light arriving from `direction` and leaving
+    /// back along `r_in`, only called for materials that return `ScatterRecord::Sampled`
+    fn eval(&self, _r_in: &Ray, _rec: &HitRecord, _direction: &Vec3) -> Colour {
+        Colour::default()
+    }
+
+    /// Radiance given off at the hit point, black for anything that is not a light
+    fn emitted(&self, _u: f32, _v: f32, _p: &Point3) -> Colour {
+        Colour::default()
+    }
+
+    /// Share of the light colliding inside a medium that a phase function scatters, σs / σt,
+    /// the rest is absorbed. Black for surfaces
+    fn albedo(&self, _u: f32, _v: f32, _p: &Point3) -> Colour {
+        Colour::default()
+    }
+
+    /// True when the scattered direction depends on the wavelength of the incoming ray, after
+    /// which a spectral path can only carry on with its hero wavelength
+    fn dispersive(&self) -> bool {
+        false
+    }
+
+    /// True when the colours returned for a ray carrying wavelengths already hold one value per
+    /// wavelength, rather than RGB still to be turned into a spectrum
+    fn spectral(&self) -> bool {
+        false
+    }
 }
@@ -0,0 +1,106 @@
+use serde::Deserialize;
+
+use crate::{
+    utils::{onb::Onb, sampler::Sampler},
+    Colour, HitRecord, Ray, Vec3,
+};
+
+use super::{
+    microfacet::{fresnel_conductor, Ggx, GgxReflectionPdf},
+    Material, ScatterRecord,
+};
+
+/// Metals with measured optical constants, sampled at 650, 550 and 450 nm for red, green and blue
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NamedMetal {
+    Gold,
+    Copper,
+    Aluminium,
+}
+
+impl NamedMetal {
+    /// Real and imaginary parts of the index of refraction
+    pub fn eta_k(&self) -> (Colour, Colour) {
+        match self {
+            Self::Gold => (
+                Colour::new(0.143, 0.375, 1.442),
+                Colour::new(3.983, 2.386, 1.603),
+            ),
+            Self::Copper => (
+                Colour::new(0.200, 0.924, 1.102),
+                Colour::new(3.913, 2.453, 2.142),
+            ),
+            Self::Aluminium => (
+                Colour::new(1.657, 0.880, 0.521),
+                Colour::new(9.224, 6.270, 4.837),
+            ),
+        }
+    }
+}
+
+/// Rough metal with a GGX microfacet distribution and exact conductor Fresnel
+pub struct Conductor {
+    eta: Colour,
+    k: Colour,
+    distribution: Ggx,
+}
+
+impl Conductor {
+    pub fn new(eta: Colour, k: Colour, roughness_u: f32, roughness_v: f32) -> Self {
+        Self {
+            eta,
+            k,
+            distribution: Ggx::new(roughness_u, roughness_v),
+        }
+    }
+
+    pub fn named(metal: NamedMetal, roughness_u: f32, roughness_v: f32) -> Self {
+        let (eta, k) = metal.eta_k();
+        Self::new(eta, k, roughness_u, roughness_v)
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        _sampler: &mut Sampler,
+    ) -> Option<ScatterRecord> {
+        let frame = Onb::with_tangent(&rec.normal, &rec.tangent);
+        let wo = frame.to_local(&r_in.direction().unit_vector().scale(-1.0));
+        if wo.z() <= 0.0 {
+            return None;
+        }
+
+        if self.distribution.is_smooth() {
+            let reflected = Vec3::reflect(&r_in.direction().unit_vector(), &rec.normal);
+            return Some(ScatterRecord::Specular {
+                attenuation: fresnel_conductor(wo.z(), self.eta, self.k),
+                ray: Ray::new(rec.p, reflected, r_in.time()),
+            });
+        }
+
+        Some(ScatterRecord::Sampled {
+            pdf: Box::new(GgxReflectionPdf::new(frame, wo, self.distribution)),
+        })
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> Colour {
+        let frame = Onb::with_tangent(&rec.normal, &rec.tangent);
+        let wo = frame.to_local(&r_in.direction().unit_vector().scale(-1.0));
+        let wi = frame.to_local(&direction.unit_vector());
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Colour::default();
+        }
+
+        // D G F / (4 cos_o cos_i), with cos_i cancelled by the cosine term
+        let h = (wo + wi).unit_vector();
+        let fresnel = fresnel_conductor(wi.dot(&h), self.eta, self.k);
+        let d = self.distribution.d(&h);
+        let g = self.distribution.g2(&wo, &wi);
+
+        fresnel.scale(d * g / (4.0 * wo.z()))
+    }
+}
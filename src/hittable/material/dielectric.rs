@@ -1,20 +1,84 @@
 use crate::{
+    hittable::HitRecord,
     scene::ray::Ray,
-    utils::{colour::Colour, random_float, vec3::Vec3},
+    utils::{colour::Colour, sampler::Sampler, vec3::Vec3},
 };
 
-use super::Material;
+use super::{
+    thin_film::{Substrate, ThinFilm},
+    Material, ScatterRecord,
+};
+
+/// Index of refraction as a function of wavelength
+#[derive(Clone, Copy)]
+pub enum Ior {
+    Constant(f32),
+    /// n = a + b / λ², with λ in micrometres
+    Cauchy {
+        a: f32,
+        b: f32,
+    },
+    /// n² = 1 + Σ bᵢ λ² / (λ² - cᵢ), with λ in micrometres and cᵢ in square micrometres, the
+    /// form glass catalogues list their coefficients in
+    Sellmeier {
+        b: [f32; 3],
+        c: [f32; 3],
+    },
+}
+
+impl Ior {
+    /// Wavelength of the helium d line, the index a glass is usually quoted at
+    const D_LINE: f32 = 587.56;
+
+    /// Index at `lambda` nanometres
+    pub fn at(&self, lambda: f32) -> f32 {
+        let l2 = (lambda * 1e-3).powi(2);
+        match self {
+            Self::Constant(n) => *n,
+            Self::Cauchy { a, b } => a + b / l2,
+            Self::Sellmeier { b, c } => {
+                let sum: f32 = b.iter().zip(c).map(|(b, c)| b * l2 / (l2 - c)).sum();
+                (1.0 + sum).max(1.0).sqrt()
+            }
+        }
+    }
+
+    fn is_constant(&self) -> bool {
+        matches!(self, Self::Constant(_))
+    }
+}
 
 pub struct Dielectric {
+    ior: Ior,
+    /// Index used for RGB rendering, where there is no wavelength to look up
     ir: f32,
+    coating: Option<ThinFilm>,
 }
 
 impl Dielectric {
     pub fn new(ir: f32) -> Self {
-        Self { ir }
+        Self::with_ior(Ior::Constant(ir))
     }
 
-    fn reflectance(cosine: f32, ref_idx: f32) -> f32 {
+    /// Glass whose index follows `ior`, which splits white light into its colours when
+    /// rendering spectrally
+    pub fn with_ior(ior: Ior) -> Self {
+        Self {
+            ior,
+            ir: ior.at(Ior::D_LINE),
+            coating: None,
+        }
+    }
+
+    /// The same glass under a thin film, which then decides how much light is reflected
+    pub fn with_coating(self, coating: ThinFilm) -> Self {
+        Self {
+            coating: Some(coating),
+            ..self
+        }
+    }
+
+    pub(super) fn reflectance(cosine: f32, ref_idx: f32) -> f32 {
         // Schlick's approximation for reflectance
         let r0 = ((1.0 - ref_idx) / (1.0 + ref_idx)).powi(2);
 
@@ -23,34 +87,119 @@ impl Dielectric {
 }
 
 impl Material for Dielectric {
-    fn scatter(
-        &self,
-        r_in: &crate::scene::ray::Ray,
-        rec: &crate::hittable::HitRecord,
-        attenuation: &mut crate::utils::colour::Colour,
-        scattered: &mut crate::scene::ray::Ray,
-    ) -> bool {
-        *attenuation = Colour::new(1.0, 1.0, 1.0);
-        let refraction_ratio = if rec.front_face {
-            1.0 / self.ir
-        } else {
-            self.ir
-        };
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut Sampler) -> Option<ScatterRecord> {
+        let ir = r_in
+            .wavelength()
+            .map_or(self.ir, |lambda| self.ior.at(lambda));
+        let refraction_ratio = if rec.front_face { 1.0 / ir } else { ir };
         let unit_direction = Vec3::unit_vector(r_in.direction());
 
         let cos_theta = unit_direction.scale(-1.0).dot(&rec.normal).min(1.0);
         let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
-
         let cannot_refract = refraction_ratio * sin_theta > 1.0;
 
-        let direction =
-            if cannot_refract || Self::reflectance(cos_theta, refraction_ratio) > random_float() {
+        let Some(coating) = &self.coating else {
+            let direction = if cannot_refract
+                || Self::reflectance(cos_theta, refraction_ratio) > sampler.random_float()
+            {
                 Vec3::reflect(&unit_direction, &rec.normal)
             } else {
                 Vec3::refract(&unit_direction, &rec.normal, refraction_ratio)
             };
 
-        *scattered = Ray::new(rec.p, direction);
-        true
+            return Some(ScatterRecord::Specular {
+                attenuation: Colour::new(1.0, 1.0, 1.0),
+                ray: Ray::new(rec.p, direction, r_in.time()),
+            });
+        };
+
+        // Past the critical angle everything is reflected, whatever the film's reflectance came
+        // to after clamping its colour
+        if cannot_refract {
+            return Some(ScatterRecord::Specular {
+                attenuation: Colour::new(1.0, 1.0, 1.0),
+                ray: Ray::new(
+                    rec.p,
+                    Vec3::reflect(&unit_direction, &rec.normal),
+                    r_in.time(),
+                ),
+            });
+        }
+
+        // The film sits on the outside of the glass, between it and the air
+        let (outside, inside) = if rec.front_face { (1.0, ir) } else { (ir, 1.0) };
+        let reflectance = coating.reflectance(
+            r_in,
+            rec,
+            cos_theta,
+            outside,
+            &Substrate::Dielectric(inside),
+        );
+
+        // One choice for every channel, made with their mean reflectance and weighted so each
+        // keeps its own share
+        let p_reflect = (reflectance.x() + reflectance.y() + reflectance.z()) / 3.0;
+        let (direction, attenuation) = if sampler.random_float() < p_reflect {
+            (
+                Vec3::reflect(&unit_direction, &rec.normal),
+                reflectance.scale(1.0 / p_reflect),
+            )
+        } else {
+            (
+                Vec3::refract(&unit_direction, &rec.normal, refraction_ratio),
+                (Colour::new(1.0, 1.0, 1.0) - reflectance).scale(1.0 / (1.0 - p_reflect)),
+            )
+        };
+
+        Some(ScatterRecord::Specular {
+            attenuation,
+            ray: Ray::new(rec.p, direction, r_in.time()),
+        })
+    }
+
+    fn dispersive(&self) -> bool {
+        !self.ior.is_constant()
+    }
+
+    fn spectral(&self) -> bool {
+        self.coating.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Point3;
+
+    use super::*;
+
+    #[test]
+    fn coated_glass_reflects_everything_past_the_critical_angle() {
+        let glass = Dielectric::new(1.5).with_coating(ThinFilm::new(1.8, 420.0, None));
+        // Leaving the glass 80 degrees off the normal, well past its critical angle of 42
+        let angle = 80.0_f32.to_radians();
+        let r_in = Ray::new(
+            Point3::new(-angle.sin(), angle.cos(), 0.0),
+            Vec3::new(angle.sin(), -angle.cos(), 0.0),
+            0.0,
+        );
+        let rec = HitRecord {
+            normal: Vec3::new(0.0, 1.0, 0.0),
+            front_face: false,
+            ..HitRecord::default()
+        };
+
+        let mut sampler = Sampler::new(1, 0);
+        for _ in 0..64 {
+            let Some(ScatterRecord::Specular { attenuation, ray }) =
+                glass.scatter(&r_in, &rec, &mut sampler)
+            else {
+                panic!("glass did not scatter specularly");
+            };
+            assert_eq!(
+                (attenuation.x(), attenuation.y(), attenuation.z()),
+                (1.0, 1.0, 1.0)
+            );
+            assert!(ray.direction().y() > 0.0);
+        }
     }
 }
@@ -0,0 +1,30 @@
+use crate::{utils::sampler::Sampler, Colour, HitRecord, Point3, Ray};
+
+use super::{Material, ScatterRecord};
+
+/// Emits the same radiance in every direction and never scatters
+#[derive(Default, Clone, Copy)]
+pub struct DiffuseLight {
+    emit: Colour,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Colour) -> Self {
+        Self { emit }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(
+        &self,
+        _r_in: &Ray,
+        _rec: &HitRecord,
+        _sampler: &mut Sampler,
+    ) -> Option<ScatterRecord> {
+        None
+    }
+
+    fn emitted(&self, _u: f32, _v: f32, _p: &Point3) -> Colour {
+        self.emit
+    }
+}
@@ -0,0 +1,94 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::{
+    hittable::{pdf::Pdf, texture::Texture},
+    utils::{onb::Onb, sampler::Sampler},
+    Colour, HitRecord, Point3, Ray, Vec3,
+};
+
+use super::{Material, ScatterRecord};
+
+/// Phase function of a participating medium that favours scattering forwards when `g` is
+/// positive and backwards when it is negative, such as haze or smoke
+pub struct HenyeyGreenstein {
+    albedo: Arc<dyn Texture>,
+    g: f32,
+}
+
+impl HenyeyGreenstein {
+    /// `g` is the mean cosine of the scattering angle and must lie strictly between -1 and 1
+    pub fn new(albedo: Arc<dyn Texture>, g: f32) -> Self {
+        Self { albedo, g }
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        _rec: &HitRecord,
+        _sampler: &mut Sampler,
+    ) -> Option<ScatterRecord> {
+        Some(ScatterRecord::Sampled {
+            pdf: Box::new(HenyeyGreensteinPdf::new(r_in.direction(), self.g)),
+        })
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> Colour {
+        let cos_theta = r_in.direction().unit_vector().dot(&direction.unit_vector());
+        self.albedo
+            .value(rec.u, rec.v, &rec.p)
+            .scale(phase(cos_theta, self.g))
+    }
+
+    fn albedo(&self, u: f32, v: f32, p: &Point3) -> Colour {
+        self.albedo.value(u, v, p)
+    }
+}
+
+/// Density of scattering through an angle with cosine `cos_theta` from the direction of travel
+fn phase(cos_theta: f32, g: f32) -> f32 {
+    let denominator = 1.0 + g * g - 2.0 * g * cos_theta;
+    (1.0 - g * g) / (4.0 * PI * denominator * denominator.max(1e-8).sqrt())
+}
+
+/// Exact importance sampling of the Henyey-Greenstein phase function around a travel direction
+pub struct HenyeyGreensteinPdf {
+    frame: Onb,
+    g: f32,
+}
+
+impl HenyeyGreensteinPdf {
+    pub fn new(direction: &Vec3, g: f32) -> Self {
+        Self {
+            frame: Onb::new(direction),
+            g,
+        }
+    }
+}
+
+impl Pdf for HenyeyGreensteinPdf {
+    fn value(&self, direction: &Vec3) -> f32 {
+        phase(direction.unit_vector().dot(&self.frame.w()), self.g)
+    }
+
+    fn generate(&self, sampler: &mut Sampler) -> Vec3 {
+        let g = self.g;
+        let xi = sampler.random_float();
+        let cos_theta = if g.abs() < 1e-3 {
+            1.0 - 2.0 * xi
+        } else {
+            let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * xi);
+            ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+        };
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * sampler.random_float();
+
+        self.frame.transform(&Vec3::new(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ))
+    }
+}
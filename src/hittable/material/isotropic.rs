@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use crate::{
+    hittable::{pdf::SpherePdf, texture::Texture},
+    utils::sampler::Sampler,
+    Colour, HitRecord, Point3, Ray, Vec3,
+};
+
+use super::{Material, ScatterRecord};
+
+/// Phase function of a participating medium that scatters equally in every direction
+pub struct Isotropic {
+    albedo: Arc<dyn Texture>,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Arc<dyn Texture>) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(
+        &self,
+        _r_in: &Ray,
+        _rec: &HitRecord,
+        _sampler: &mut Sampler,
+    ) -> Option<ScatterRecord> {
+        Some(ScatterRecord::Sampled {
+            pdf: Box::new(SpherePdf),
+        })
+    }
+
+    // There is no surface, so no cosine either
+    fn eval(&self, _r_in: &Ray, rec: &HitRecord, _direction: &Vec3) -> Colour {
+        self.albedo
+            .value(rec.u, rec.v, &rec.p)
+            .scale(1.0 / (4.0 * std::f32::consts::PI))
+    }
+
+    fn albedo(&self, u: f32, v: f32, p: &Point3) -> Colour {
+        self.albedo.value(u, v, p)
+    }
+}
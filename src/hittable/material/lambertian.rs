@@ -1,34 +1,53 @@
-use crate::{Colour, HitRecord, Ray, Vec3};
+use std::sync::Arc;
 
-use super::Material;
+use crate::{
+    hittable::{
+        pdf::CosinePdf,
+        texture::{solid_colour::SolidColour, Texture},
+    },
+    utils::sampler::Sampler,
+    Colour, HitRecord, Ray, Vec3,
+};
 
-#[derive(Default, Clone, Copy)]
+use super::{Material, ScatterRecord};
+
+#[derive(Clone)]
 pub struct Lambertian {
-    albedo: Colour,
+    albedo: Arc<dyn Texture>,
 }
 
 impl Lambertian {
     pub fn new(albedo: Colour) -> Self {
+        Self::from_texture(Arc::new(SolidColour::new(albedo)))
+    }
+
+    pub fn from_texture(albedo: Arc<dyn Texture>) -> Self {
         Self { albedo }
     }
 }
 
+impl Default for Lambertian {
+    fn default() -> Self {
+        Self::new(Colour::default())
+    }
+}
+
 impl Material for Lambertian {
     fn scatter(
         &self,
         _r_in: &Ray,
         rec: &HitRecord,
-        attenuation: &mut Colour,
-        scattered: &mut Ray,
-    ) -> bool {
-        let mut scatter_direction = rec.normal + Vec3::random_unit_vector();
-
-        if scatter_direction.near_zero() {
-            scatter_direction = rec.normal;
-        }
-
-        *scattered = Ray::new(rec.p, scatter_direction);
-        *attenuation = self.albedo;
-        return true;
+        _sampler: &mut Sampler,
+    ) -> Option<ScatterRecord> {
+        Some(ScatterRecord::Sampled {
+            pdf: Box::new(CosinePdf::new(&rec.normal)),
+        })
+    }
+
+    fn eval(&self, _r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> Colour {
+        let cosine = rec.normal.dot(&direction.unit_vector()).max(0.0);
+        self.albedo
+            .value(rec.u, rec.v, &rec.p)
+            .scale(cosine / std::f32::consts::PI)
     }
 }
@@ -1,37 +1,73 @@
-use crate::{Colour, Ray, Vec3};
+use std::sync::Arc;
 
-use super::Material;
+use crate::{
+    hittable::texture::{solid_colour::SolidColour, Texture},
+    utils::sampler::Sampler,
+    Colour, HitRecord, Ray, Vec3,
+};
+
+use super::{
+    thin_film::{Substrate, ThinFilm},
+    Material, ScatterRecord,
+};
 
-#[derive(Default, Clone, Copy)]
 pub struct Metal {
-    albedo: Colour,
+    albedo: Arc<dyn Texture>,
     fuzz: f32,
+    coating: Option<ThinFilm>,
 }
 
 impl Metal {
     pub fn new(albedo: Colour, fuzz: f32) -> Self {
+        Self::from_texture(Arc::new(SolidColour::new(albedo)), fuzz)
+    }
+
+    pub fn from_texture(albedo: Arc<dyn Texture>, fuzz: f32) -> Self {
         Self {
             albedo,
             fuzz: fuzz.min(1.0),
+            coating: None,
+        }
+    }
+
+    /// The same metal under a thin film, like the oxide layer of anodised titanium
+    pub fn with_coating(self, coating: ThinFilm) -> Self {
+        Self {
+            coating: Some(coating),
+            ..self
         }
     }
 }
 
 impl Material for Metal {
-    fn scatter(
-        &self,
-        r_in: &crate::scene::ray::Ray,
-        rec: &crate::hittable::HitRecord,
-        attenuation: &mut Colour,
-        scattered: &mut crate::scene::ray::Ray,
-    ) -> bool {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut Sampler) -> Option<ScatterRecord> {
         let reflected = Vec3::reflect(&Vec3::unit_vector(r_in.direction()), &rec.normal);
-        *scattered = Ray::new(
+        let scattered = Ray::new(
             rec.p,
-            reflected + Vec3::random_unit_vector().scale(self.fuzz),
+            reflected + Vec3::random_unit_vector(sampler).scale(self.fuzz),
+            r_in.time(),
         );
-        *attenuation = self.albedo;
 
-        scattered.direction().dot(&rec.normal) > 0.0
+        if scattered.direction().dot(&rec.normal) <= 0.0 {
+            return None;
+        }
+
+        let albedo = self.albedo.value(rec.u, rec.v, &rec.p);
+        let attenuation = match &self.coating {
+            Some(coating) => {
+                let cos_theta = -Vec3::unit_vector(r_in.direction()).dot(&rec.normal);
+                coating.reflectance(r_in, rec, cos_theta, 1.0, &Substrate::Metal(albedo))
+            }
+            None => albedo,
+        };
+
+        Some(ScatterRecord::Specular {
+            attenuation,
+            ray: scattered,
+        })
+    }
+
+    fn spectral(&self) -> bool {
+        self.coating.is_some()
     }
 }
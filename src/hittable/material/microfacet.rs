@@ -0,0 +1,171 @@
+//! GGX (Trowbridge-Reitz) microfacet distribution with Smith masking-shadowing
+//!
+//! Directions are in a local frame where the surface normal is +z.
+
+use std::f32::consts::PI;
+
+use crate::{
+    hittable::pdf::Pdf,
+    utils::{onb::Onb, sampler::Sampler},
+    Colour, Vec3,
+};
+
+#[derive(Clone, Copy)]
+pub struct Ggx {
+    alpha_x: f32,
+    alpha_y: f32,
+}
+
+impl Ggx {
+    /// Roughness is perceptual, the distribution width is its square. `roughness_u` applies along
+    /// the tangent of the surface frame and `roughness_v` along the bitangent
+    pub fn new(roughness_u: f32, roughness_v: f32) -> Self {
+        // Keeps D finite, anything this narrow is treated as a mirror by `is_smooth`
+        let alpha = |roughness: f32| (roughness * roughness).max(1e-4);
+
+        Self {
+            alpha_x: alpha(roughness_u),
+            alpha_y: alpha(roughness_v),
+        }
+    }
+
+    /// Narrow enough that sampling it as a perfect mirror makes no visible difference
+    pub fn is_smooth(&self) -> bool {
+        self.alpha_x.max(self.alpha_y) < 1e-3
+    }
+
+    /// Density of microfacet normals `h`
+    pub fn d(&self, h: &Vec3) -> f32 {
+        if h.z() <= 0.0 {
+            return 0.0;
+        }
+
+        let e = (h.x() / self.alpha_x).powi(2) + (h.y() / self.alpha_y).powi(2) + h.z() * h.z();
+        1.0 / (PI * self.alpha_x * self.alpha_y * e * e)
+    }
+
+    fn lambda(&self, w: &Vec3) -> f32 {
+        let cos2 = w.z() * w.z();
+        if cos2 == 0.0 {
+            return f32::INFINITY;
+        }
+
+        let tan2 = ((self.alpha_x * w.x()).powi(2) + (self.alpha_y * w.y()).powi(2)) / cos2;
+        ((1.0 + tan2).sqrt() - 1.0) / 2.0
+    }
+
+    /// Fraction of microfacets visible from `w`
+    pub fn g1(&self, w: &Vec3) -> f32 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// Height-correlated masking and shadowing for the pair `wo`, `wi`
+    pub fn g2(&self, wo: &Vec3, wi: &Vec3) -> f32 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Samples a microfacet normal visible from `wo` (Heitz 2018), `wo` must be above the surface
+    pub fn sample_visible_normal(&self, wo: &Vec3, sampler: &mut Sampler) -> Vec3 {
+        // Stretch the view direction so the distribution becomes a hemisphere
+        let vh = Vec3::new(self.alpha_x * wo.x(), self.alpha_y * wo.y(), wo.z()).unit_vector();
+
+        let lensq = vh.x() * vh.x() + vh.y() * vh.y();
+        let t1 = if lensq > 0.0 {
+            Vec3::new(-vh.y(), vh.x(), 0.0).scale(1.0 / lensq.sqrt())
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = vh.cross(&t1);
+
+        // Uniform point on a disk, warped onto the visible half of the projected hemisphere
+        let r = sampler.random_float().sqrt();
+        let phi = 2.0 * PI * sampler.random_float();
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z());
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * r * phi.sin();
+
+        let nh = t1.scale(p1) + t2.scale(p2) + vh.scale((1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt());
+
+        Vec3::new(
+            self.alpha_x * nh.x(),
+            self.alpha_y * nh.y(),
+            nh.z().max(0.0),
+        )
+        .unit_vector()
+    }
+
+    /// Solid angle density of reflecting `wo` into `wi` through `sample_visible_normal`
+    pub fn reflection_pdf(&self, wo: &Vec3, wi: &Vec3) -> f32 {
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+
+        let h = (*wo + *wi).unit_vector();
+        self.g1(wo) * self.d(&h) / (4.0 * wo.z())
+    }
+}
+
+/// Reflection off GGX normals visible from `wo`, given in the local coordinates of `frame`
+pub struct GgxReflectionPdf {
+    frame: Onb,
+    wo: Vec3,
+    distribution: Ggx,
+}
+
+impl GgxReflectionPdf {
+    pub fn new(frame: Onb, wo: Vec3, distribution: Ggx) -> Self {
+        Self {
+            frame,
+            wo,
+            distribution,
+        }
+    }
+}
+
+impl Pdf for GgxReflectionPdf {
+    fn value(&self, direction: &Vec3) -> f32 {
+        let wi = self.frame.to_local(&direction.unit_vector());
+        self.distribution.reflection_pdf(&self.wo, &wi)
+    }
+
+    fn generate(&self, sampler: &mut Sampler) -> Vec3 {
+        let h = self.distribution.sample_visible_normal(&self.wo, sampler);
+        let wi = Vec3::reflect(&self.wo.scale(-1.0), &h);
+        self.frame.transform(&wi)
+    }
+}
+
+/// Schlick's approximation with a coloured reflectance at normal incidence
+pub fn fresnel_schlick(cos_theta: f32, f0: Colour) -> Colour {
+    let weight = (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5);
+    f0.scale(1.0 - weight) + Colour::new(weight, weight, weight)
+}
+
+/// Fresnel reflectance of a conductor with complex index of refraction `eta` + i`k` per channel,
+/// seen from air
+pub fn fresnel_conductor(cos_theta: f32, eta: Colour, k: Colour) -> Colour {
+    let channel = |eta: f32, k: f32| {
+        let cos2 = cos_theta.clamp(0.0, 1.0).powi(2);
+        let sin2 = 1.0 - cos2;
+        let (eta2, k2) = (eta * eta, k * k);
+
+        let t0 = eta2 - k2 - sin2;
+        let a2_plus_b2 = (t0 * t0 + 4.0 * eta2 * k2).sqrt();
+        let t1 = a2_plus_b2 + cos2;
+        let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+        let t2 = 2.0 * cos_theta * a;
+        let rs = (t1 - t2) / (t1 + t2);
+
+        let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+        let t4 = t2 * sin2;
+        let rp = rs * (t3 - t4) / (t3 + t4);
+
+        0.5 * (rp + rs)
+    };
+
+    Colour::new(
+        channel(eta.x(), k.x()),
+        channel(eta.y(), k.y()),
+        channel(eta.z(), k.z()),
+    )
+}
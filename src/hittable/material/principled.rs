@@ -0,0 +1,208 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::{
+    hittable::{
+        pdf::{CosinePdf, MixturePdf, Pdf},
+        texture::{solid_colour::SolidColour, Texture},
+    },
+    utils::{onb::Onb, sampler::Sampler},
+    Colour, HitRecord, Ray, Vec3,
+};
+
+use super::{
+    microfacet::{fresnel_schlick, Ggx, GgxReflectionPdf},
+    rough_dielectric::RoughDielectric,
+    Material, ScatterRecord,
+};
+
+// Roughness of the clear coat layer, which has no parameter of its own
+const CLEARCOAT_ROUGHNESS: f32 = 0.25;
+
+/// Disney-style uber material blending a diffuse base, a metallic or dielectric specular lobe,
+/// sheen, a clear coat and rough glass transmission
+///
+/// Every parameter except `ior` is a texture, scalar parameters read the average of its channels.
+/// Build one with struct update syntax over `Principled::default()`.
+pub struct Principled {
+    pub base_colour: Arc<dyn Texture>,
+    /// 0 is a dielectric, 1 a metal tinted by the base colour
+    pub metallic: Arc<dyn Texture>,
+    pub roughness: Arc<dyn Texture>,
+    /// Dielectric reflectance at normal incidence scaled so that 0.5 is 4%
+    pub specular: Arc<dyn Texture>,
+    /// Soft grazing highlight for cloth
+    pub sheen: Arc<dyn Texture>,
+    pub clearcoat: Arc<dyn Texture>,
+    /// Fraction of the dielectric base that refracts like glass instead of scattering diffusely
+    pub transmission: Arc<dyn Texture>,
+    pub ior: f32,
+}
+
+impl Default for Principled {
+    fn default() -> Self {
+        let constant =
+            |x: f32| -> Arc<dyn Texture> { Arc::new(SolidColour::new(Colour::new(x, x, x))) };
+
+        Self {
+            base_colour: constant(0.8),
+            metallic: constant(0.0),
+            roughness: constant(0.5),
+            specular: constant(0.5),
+            sheen: constant(0.0),
+            clearcoat: constant(0.0),
+            transmission: constant(0.0),
+            ior: 1.5,
+        }
+    }
+}
+
+/// Parameters looked up at one hit point
+struct Parameters {
+    base_colour: Colour,
+    metallic: f32,
+    roughness: f32,
+    specular: f32,
+    sheen: f32,
+    clearcoat: f32,
+    transmission: f32,
+}
+
+impl Parameters {
+    /// Probability of following the glass lobe instead of the reflective ones
+    fn transmission_probability(&self) -> f32 {
+        (1.0 - self.metallic) * self.transmission
+    }
+
+    fn specular_f0(&self) -> Colour {
+        let dielectric = 0.08 * self.specular;
+        Colour::new(dielectric, dielectric, dielectric).scale(1.0 - self.metallic)
+            + self.base_colour.scale(self.metallic)
+    }
+}
+
+impl Principled {
+    fn parameters(&self, rec: &HitRecord) -> Parameters {
+        let scalar = |texture: &Arc<dyn Texture>| {
+            let c = texture.value(rec.u, rec.v, &rec.p);
+            ((c.x() + c.y() + c.z()) / 3.0).clamp(0.0, 1.0)
+        };
+
+        Parameters {
+            base_colour: self.base_colour.value(rec.u, rec.v, &rec.p),
+            metallic: scalar(&self.metallic),
+            roughness: scalar(&self.roughness),
+            specular: scalar(&self.specular),
+            sheen: scalar(&self.sheen),
+            clearcoat: scalar(&self.clearcoat),
+            transmission: scalar(&self.transmission),
+        }
+    }
+}
+
+impl Material for Principled {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut Sampler) -> Option<ScatterRecord> {
+        let params = self.parameters(rec);
+
+        // Glass is picked stochastically, `eval` then only has to cover the reflective lobes
+        if sampler.random_float() < params.transmission_probability() {
+            let glass = RoughDielectric::new(self.ior, params.roughness, params.roughness);
+            return match glass.scatter(r_in, rec, sampler)? {
+                ScatterRecord::Specular { attenuation, ray } => {
+                    // Tint once on the way in and once on the way out
+                    let refracted = ray.direction().dot(&rec.normal) < 0.0;
+                    let tint = if refracted {
+                        let c = params.base_colour;
+                        Colour::new(c.x().sqrt(), c.y().sqrt(), c.z().sqrt())
+                    } else {
+                        Colour::new(1.0, 1.0, 1.0)
+                    };
+                    Some(ScatterRecord::Specular {
+                        attenuation: attenuation * tint,
+                        ray,
+                    })
+                }
+                sampled => Some(sampled),
+            };
+        }
+
+        let frame = Onb::with_tangent(&rec.normal, &rec.tangent);
+        let wo = frame.to_local(&r_in.direction().unit_vector().scale(-1.0));
+        if wo.z() <= 0.0 {
+            return None;
+        }
+
+        let diffuse_weight = (1.0 - params.metallic) * (1.0 - params.transmission);
+        let lobes: Vec<(f32, Box<dyn Pdf>)> = vec![
+            (diffuse_weight, Box::new(CosinePdf::new(&rec.normal))),
+            (
+                1.0,
+                Box::new(GgxReflectionPdf::new(
+                    frame.clone(),
+                    wo,
+                    Ggx::new(params.roughness, params.roughness),
+                )),
+            ),
+            (
+                0.25 * params.clearcoat,
+                Box::new(GgxReflectionPdf::new(
+                    frame,
+                    wo,
+                    Ggx::new(CLEARCOAT_ROUGHNESS, CLEARCOAT_ROUGHNESS),
+                )),
+            ),
+        ];
+
+        Some(ScatterRecord::Sampled {
+            pdf: Box::new(MixturePdf::new(lobes)),
+        })
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> Colour {
+        let params = self.parameters(rec);
+        let reflect_probability = 1.0 - params.transmission_probability();
+        if reflect_probability <= 0.0 {
+            return Colour::default();
+        }
+
+        let frame = Onb::with_tangent(&rec.normal, &rec.tangent);
+        let wo = frame.to_local(&r_in.direction().unit_vector().scale(-1.0));
+        let wi = frame.to_local(&direction.unit_vector());
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Colour::default();
+        }
+
+        let h = (wo + wi).unit_vector();
+        let cos_d = wi.dot(&h);
+        let schlick = |cos: f32| (1.0 - cos).clamp(0.0, 1.0).powi(5);
+
+        // Burley's diffuse with grazing retro-reflection, plus sheen
+        let fd90 = 0.5 + 2.0 * params.roughness * cos_d * cos_d;
+        let fd = (1.0 + (fd90 - 1.0) * schlick(wi.z())) * (1.0 + (fd90 - 1.0) * schlick(wo.z()));
+        let sheen = params.sheen * schlick(cos_d);
+        let base = params.base_colour.scale(fd / PI) + Colour::new(sheen, sheen, sheen);
+        let diffuse_weight = (1.0 - params.metallic) * (1.0 - params.transmission);
+
+        // Every microfacet lobe is D G F / (4 cos_o cos_i), with cos_i cancelled by the cosine term
+        let microfacet = |distribution: Ggx, f0: Colour| {
+            let d = distribution.d(&h);
+            let g = distribution.g2(&wo, &wi);
+            fresnel_schlick(cos_d, f0).scale(d * g / (4.0 * wo.z()))
+        };
+
+        // The glass lobe brings its own reflection, so the specular lobe covers the rest
+        let specular = microfacet(
+            Ggx::new(params.roughness, params.roughness),
+            params.specular_f0(),
+        )
+        .scale(params.metallic + diffuse_weight);
+        let clearcoat = microfacet(
+            Ggx::new(CLEARCOAT_ROUGHNESS, CLEARCOAT_ROUGHNESS),
+            Colour::new(0.04, 0.04, 0.04),
+        )
+        .scale(0.25 * params.clearcoat);
+
+        (base.scale(diffuse_weight * wi.z()) + specular + clearcoat)
+            .scale(1.0 / reflect_probability)
+    }
+}
@@ -0,0 +1,74 @@
+use crate::{
+    utils::{onb::Onb, sampler::Sampler},
+    Colour, HitRecord, Ray, Vec3,
+};
+
+use super::{dielectric::Dielectric, microfacet::Ggx, Material, ScatterRecord};
+
+/// Frosted glass, `Dielectric` refraction through GGX microfacet normals
+///
+/// Like smooth glass it picks one outgoing ray itself instead of being sampled towards lights.
+pub struct RoughDielectric {
+    ir: f32,
+    distribution: Ggx,
+}
+
+impl RoughDielectric {
+    pub fn new(ir: f32, roughness_u: f32, roughness_v: f32) -> Self {
+        Self {
+            ir,
+            distribution: Ggx::new(roughness_u, roughness_v),
+        }
+    }
+}
+
+impl Material for RoughDielectric {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut Sampler) -> Option<ScatterRecord> {
+        let frame = Onb::with_tangent(&rec.normal, &rec.tangent);
+        let wo = frame.to_local(&r_in.direction().unit_vector().scale(-1.0));
+        if wo.z() <= 0.0 {
+            return None;
+        }
+
+        let h = if self.distribution.is_smooth() {
+            Vec3::new(0.0, 0.0, 1.0)
+        } else {
+            self.distribution.sample_visible_normal(&wo, sampler)
+        };
+
+        let refraction_ratio = if rec.front_face {
+            1.0 / self.ir
+        } else {
+            self.ir
+        };
+        let cos_theta = wo.dot(&h).min(1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let cannot_refract = refraction_ratio * sin_theta > 1.0;
+
+        let reflect = cannot_refract
+            || Dielectric::reflectance(cos_theta, refraction_ratio) > sampler.random_float();
+        let wi = if reflect {
+            Vec3::reflect(&wo.scale(-1.0), &h)
+        } else {
+            Vec3::refract(&wo.scale(-1.0), &h, refraction_ratio)
+        };
+
+        // Rays the microfacet sends to the wrong side of the macro surface are lost
+        if (wi.z() > 0.0) != reflect {
+            return None;
+        }
+
+        // Visible normal sampling leaves only the shadowing term as the weight
+        let weight = if self.distribution.is_smooth() {
+            1.0
+        } else {
+            let wi_above = Vec3::new(wi.x(), wi.y(), wi.z().abs());
+            self.distribution.g2(&wo, &wi_above) / self.distribution.g1(&wo)
+        };
+
+        Some(ScatterRecord::Specular {
+            attenuation: Colour::new(weight, weight, weight),
+            ray: Ray::new(rec.p, frame.transform(&wi), r_in.time()),
+        })
+    }
+}
@@ -0,0 +1,167 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::{
+    hittable::texture::Texture,
+    utils::spectrum::{self, SpectralFilm, Wavelengths, LAMBDA_MAX, LAMBDA_MIN},
+    Colour, HitRecord, Ray,
+};
+
+/// What lies beneath a thin film
+pub enum Substrate {
+    /// Transparent medium with this index of refraction
+    Dielectric(f32),
+    /// Mirror-like metal reflecting this colour, assumed to flip the phase of the light it
+    /// reflects like a perfect conductor
+    Metal(Colour),
+}
+
+/// Transparent coating a few hundred nanometres thick, like a soap film, an oil slick or the
+/// oxide layer of anodised metal
+///
+/// Light reflected from the top of the film interferes with light reflected from beneath it,
+/// so the reflectance rises and falls with wavelength, thickness and angle. Reflectance is
+/// found with the Airy sum over every bounce inside the film, for each polarisation.
+pub struct ThinFilm {
+    ior: f32,
+    /// Thickness in nanometres
+    thickness: f32,
+    /// Scales `thickness` by the mean of its channels
+    texture: Option<Arc<dyn Texture>>,
+    /// Wavelengths spread over the visible range and their share of linear sRGB, for turning
+    /// reflectance spectra into colours when rendering in RGB
+    response: Vec<(f32, Colour)>,
+}
+
+impl ThinFilm {
+    /// Wavelengths the reflectance is averaged over in RGB rendering, enough to follow the
+    /// fringes of films up to about a micrometre thick
+    const RGB_SAMPLES: usize = 64;
+
+    pub fn new(ior: f32, thickness: f32, texture: Option<Arc<dyn Texture>>) -> Self {
+        let film = SpectralFilm::default();
+        let step = (LAMBDA_MAX - LAMBDA_MIN) / Self::RGB_SAMPLES as f32;
+        let response = (0..Self::RGB_SAMPLES)
+            .map(|i| {
+                let lambda = LAMBDA_MIN + (i as f32 + 0.5) * step;
+                (
+                    lambda,
+                    film.response(lambda).scale(1.0 / Self::RGB_SAMPLES as f32),
+                )
+            })
+            .collect();
+
+        Self {
+            ior,
+            thickness,
+            texture,
+            response,
+        }
+    }
+
+    /// Reflectance of the coated `substrate` at `rec` for light arriving at `cos_theta` from a
+    /// medium with index `outside`, one value per wavelength when `r_in` carries wavelengths
+    /// and a linear sRGB colour otherwise
+    pub fn reflectance(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        cos_theta: f32,
+        outside: f32,
+        substrate: &Substrate,
+    ) -> Colour {
+        let thickness = match &self.texture {
+            Some(texture) => {
+                let scale = texture.value(rec.u, rec.v, &rec.p);
+                self.thickness * (scale.x() + scale.y() + scale.z()) / 3.0
+            }
+            None => self.thickness,
+        };
+        let at = |lambda: f32| {
+            let substrate = match substrate {
+                Substrate::Dielectric(ior) => Interface::Dielectric(*ior),
+                Substrate::Metal(colour) => Interface::Mirror(spectrum::upsample(colour, lambda)),
+            };
+            self.airy(lambda, thickness, cos_theta, outside, substrate)
+        };
+
+        match r_in.wavelength() {
+            Some(hero) => {
+                let [a, b, c] = Wavelengths::new(hero).lambdas().map(at);
+                Colour::new(a, b, c)
+            }
+            None => {
+                // The sRGB response dips below zero at some wavelengths, so a narrow fringe can
+                // fold to a channel outside [0, 1]
+                let rgb = self
+                    .response
+                    .iter()
+                    .fold(Colour::default(), |sum, (lambda, weight)| {
+                        sum + weight.scale(at(*lambda))
+                    });
+                Colour::new(
+                    rgb.x().clamp(0.0, 1.0),
+                    rgb.y().clamp(0.0, 1.0),
+                    rgb.z().clamp(0.0, 1.0),
+                )
+            }
+        }
+    }
+
+    /// Reflectance at one wavelength, averaged over both polarisations
+    fn airy(
+        &self,
+        lambda: f32,
+        thickness: f32,
+        cos_theta: f32,
+        outside: f32,
+        substrate: Interface,
+    ) -> f32 {
+        let n1 = outside;
+        let n2 = self.ior;
+        let cos1 = cos_theta.clamp(0.0, 1.0);
+        let sin1_squared = 1.0 - cos1 * cos1;
+
+        // Cosine of the angle of the ray refracted into a layer of index `n`, None past the
+        // critical angle, where everything is reflected
+        let refracted = |n: f32| {
+            let sin_squared = (n1 / n).powi(2) * sin1_squared;
+            (sin_squared < 1.0).then(|| (1.0 - sin_squared).sqrt())
+        };
+        let Some(cos2) = refracted(n2) else {
+            return 1.0;
+        };
+        let (r23_s, r23_p) = match substrate {
+            Interface::Dielectric(n3) => {
+                let Some(cos3) = refracted(n3) else {
+                    return 1.0;
+                };
+                (
+                    (n2 * cos2 - n3 * cos3) / (n2 * cos2 + n3 * cos3),
+                    (n3 * cos2 - n2 * cos3) / (n3 * cos2 + n2 * cos3),
+                )
+            }
+            Interface::Mirror(reflectance) => {
+                let r = -reflectance.clamp(0.0, 1.0).sqrt();
+                (r, r)
+            }
+        };
+        let r12_s = (n1 * cos1 - n2 * cos2) / (n1 * cos1 + n2 * cos2);
+        let r12_p = (n2 * cos1 - n1 * cos2) / (n2 * cos1 + n1 * cos2);
+
+        // Phase gained by one round trip through the film
+        let cos_delta = (4.0 * PI * n2 * thickness * cos2 / lambda).cos();
+        let airy = |a: f32, b: f32| {
+            let cross = 2.0 * a * b * cos_delta;
+            (a * a + b * b + cross) / (1.0 + a * a * b * b + cross)
+        };
+
+        0.5 * (airy(r12_s, r23_s) + airy(r12_p, r23_p))
+    }
+}
+
+/// `Substrate` at a single wavelength
+enum Interface {
+    Dielectric(f32),
+    Mirror(f32),
+}
@@ -0,0 +1,165 @@
+use std::sync::Arc;
+
+use crate::utils::interval::Interval;
+use crate::{Hittable, Point3, Ray, Vec3};
+
+use super::aabb::Aabb;
+use super::bvh::BvhNode;
+use super::material::Material;
+use super::{HitRecord, HittableList};
+
+/// Corner of a mesh face, indices point into the shared vertex attribute arrays
+#[derive(Clone, Copy, Debug)]
+pub struct MeshVertex {
+    pub position: usize,
+    pub normal: Option<usize>,
+    pub texcoord: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct MeshFace {
+    pub vertices: [MeshVertex; 3],
+    /// Index into `MeshData::materials`
+    pub material: usize,
+}
+
+/// Vertex attributes and faces shared by every triangle of a mesh
+#[derive(Default)]
+pub struct MeshData {
+    pub positions: Vec<Point3>,
+    pub normals: Vec<Vec3>,
+    pub texcoords: Vec<[f32; 2]>,
+    pub faces: Vec<MeshFace>,
+    pub materials: Vec<Arc<dyn Material>>,
+}
+
+/// Triangle mesh with its own BVH over the faces
+pub struct Mesh {
+    bvh: BvhNode,
+}
+
+impl Mesh {
+    pub fn new(data: MeshData) -> Self {
+        let data = Arc::new(data);
+
+        let mut triangles = HittableList::new();
+        for face in 0..data.faces.len() {
+            triangles.add(Box::new(MeshTriangle::new(Arc::clone(&data), face)));
+        }
+
+        Self {
+            bvh: triangles.into_bvh(),
+        }
+    }
+}
+
+impl Hittable for Mesh {
+    fn hit(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        self.bvh.hit(r, ray_t, rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bvh.bounding_box()
+    }
+}
+
+struct MeshTriangle {
+    data: Arc<MeshData>,
+    face: usize,
+}
+
+impl MeshTriangle {
+    fn new(data: Arc<MeshData>, face: usize) -> Self {
+        Self { data, face }
+    }
+
+    fn positions(&self) -> [Point3; 3] {
+        self.data.faces[self.face]
+            .vertices
+            .map(|vertex| self.data.positions[vertex.position])
+    }
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        let face = &self.data.faces[self.face];
+        let [a, b, c] = self.positions();
+        let e1 = b - a;
+        let e2 = c - a;
+
+        // Möller-Trumbore, see `Triangle::hit`
+        let pvec = r.direction().cross(&e2);
+        let det = e1.dot(&pvec);
+        if det.abs() < 1e-12 {
+            return false;
+        }
+
+        let inv_det = 1.0 / det;
+        let tvec = *r.origin() - a;
+        let beta = tvec.dot(&pvec) * inv_det;
+        if !(0.0..=1.0).contains(&beta) {
+            return false;
+        }
+
+        let qvec = tvec.cross(&e1);
+        let gamma = r.direction().dot(&qvec) * inv_det;
+        if gamma < 0.0 || beta + gamma > 1.0 {
+            return false;
+        }
+
+        let t = e2.dot(&qvec) * inv_det;
+        if !ray_t.surrounds(t) {
+            return false;
+        }
+
+        let alpha = 1.0 - beta - gamma;
+        let weights = [alpha, beta, gamma];
+        let interpolate = |values: [Vec3; 3]| {
+            values[0].scale(weights[0]) + values[1].scale(weights[1]) + values[2].scale(weights[2])
+        };
+
+        rec.t = t;
+        rec.p = r.at(t);
+
+        (rec.u, rec.v, rec.tangent) = match face.vertices.map(|vertex| vertex.texcoord) {
+            [Some(ta), Some(tb), Some(tc)] => {
+                let [ta, tb, tc] = [ta, tb, tc].map(|i| self.data.texcoords[i]);
+                let uv = interpolate([ta, tb, tc].map(|t| Vec3::new(t[0], t[1], 0.0)));
+                // Solves e1 = du1 dp/du + dv1 dp/dv and e2 = du2 dp/du + dv2 dp/dv for dp/du
+                let (du1, dv1) = (tb[0] - ta[0], tb[1] - ta[1]);
+                let (du2, dv2) = (tc[0] - ta[0], tc[1] - ta[1]);
+                let det = du1 * dv2 - dv1 * du2;
+                let tangent = if det.abs() > 1e-12 {
+                    (e1.scale(dv2) - e2.scale(dv1)).scale(1.0 / det)
+                } else {
+                    e1
+                };
+                (uv.x(), uv.y(), tangent)
+            }
+            _ => (beta, gamma, e1),
+        };
+
+        // Which side was hit always follows the winding, vertex normals only shade
+        let geometric_normal = e1.cross(&e2).unit_vector();
+        rec.set_face_normal(r, &geometric_normal);
+
+        if let [Some(na), Some(nb), Some(nc)] = face.vertices.map(|vertex| vertex.normal) {
+            let mut shading_normal =
+                interpolate([na, nb, nc].map(|i| self.data.normals[i])).unit_vector();
+            if shading_normal.dot(&rec.normal) < 0.0 {
+                shading_normal = shading_normal.scale(-1.0);
+            }
+            if !shading_normal.near_zero() && shading_normal.x().is_finite() {
+                rec.normal = shading_normal;
+            }
+        }
+
+        rec.mat = Arc::clone(&self.data.materials[face.material]);
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let [a, b, c] = self.positions();
+        Aabb::enclosing(&Aabb::from_points(a, b), &Aabb::from_points(a, c))
+    }
+}
@@ -0,0 +1,95 @@
+use crate::{
+    utils::{onb::Onb, sampler::Sampler},
+    Vec3,
+};
+
+/// Probability density over directions that can also draw samples from itself
+pub trait Pdf {
+    /// Density with respect to solid angle of sampling `direction`
+    fn value(&self, direction: &Vec3) -> f32;
+
+    fn generate(&self, sampler: &mut Sampler) -> Vec3;
+}
+
+/// Cosine weighted hemisphere around a normal, the ideal density for Lambertian surfaces
+pub struct CosinePdf {
+    uvw: Onb,
+}
+
+impl CosinePdf {
+    pub fn new(w: &Vec3) -> Self {
+        Self { uvw: Onb::new(w) }
+    }
+}
+
+impl Pdf for CosinePdf {
+    fn value(&self, direction: &Vec3) -> f32 {
+        let cosine_theta = direction.unit_vector().dot(&self.uvw.w());
+        (cosine_theta / std::f32::consts::PI).max(0.0)
+    }
+
+    fn generate(&self, sampler: &mut Sampler) -> Vec3 {
+        self.uvw.transform(&Vec3::random_cosine_direction(sampler))
+    }
+}
+
+/// Uniform density over every direction
+pub struct SpherePdf;
+
+impl Pdf for SpherePdf {
+    fn value(&self, _direction: &Vec3) -> f32 {
+        1.0 / (4.0 * std::f32::consts::PI)
+    }
+
+    fn generate(&self, sampler: &mut Sampler) -> Vec3 {
+        Vec3::random_unit_vector(sampler)
+    }
+}
+
+/// Weighted blend of densities, each picked for sampling in proportion to its weight
+pub struct MixturePdf {
+    components: Vec<(f32, Box<dyn Pdf>)>,
+    total_weight: f32,
+}
+
+impl MixturePdf {
+    /// Components with a weight of zero or less are dropped
+    pub fn new(components: Vec<(f32, Box<dyn Pdf>)>) -> Self {
+        let components: Vec<_> = components
+            .into_iter()
+            .filter(|(weight, _)| *weight > 0.0)
+            .collect();
+        let total_weight = components.iter().map(|(weight, _)| weight).sum();
+
+        Self {
+            components,
+            total_weight,
+        }
+    }
+}
+
+impl Pdf for MixturePdf {
+    fn value(&self, direction: &Vec3) -> f32 {
+        let sum: f32 = self
+            .components
+            .iter()
+            .map(|(weight, pdf)| weight * pdf.value(direction))
+            .sum();
+        sum / self.total_weight
+    }
+
+    fn generate(&self, sampler: &mut Sampler) -> Vec3 {
+        let mut pick = sampler.random_float() * self.total_weight;
+        for (weight, pdf) in &self.components {
+            if pick < *weight {
+                return pdf.generate(sampler);
+            }
+            pick -= weight;
+        }
+
+        // Rounding can leave `pick` just past the last weight
+        self.components[self.components.len() - 1]
+            .1
+            .generate(sampler)
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use crate::utils::{interval::Interval, sampler::Sampler};
+use crate::{Hittable, Point3, Ray, Vec3};
+
+use super::aabb::Aabb;
+use super::material::Material;
+use super::{HitRecord, HittableList};
+
+/// Parallelogram with corner `q` and edges `u` and `v`
+pub struct Quad {
+    q: Point3,
+    u: Vec3,
+    v: Vec3,
+    // Plane normal scaled by 1 / |u x v|², used to find the hit's planar coordinates
+    w: Vec3,
+    normal: Vec3,
+    d: f32,
+    area: f32,
+    mat: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+impl Quad {
+    pub fn new(q: Point3, u: Vec3, v: Vec3, mat: Arc<dyn Material>) -> Self {
+        let n = u.cross(&v);
+        let normal = n.unit_vector();
+
+        Quad {
+            q,
+            u,
+            v,
+            w: n.scale(1.0 / n.dot(&n)),
+            normal,
+            d: normal.dot(&q),
+            area: n.length(),
+            mat,
+            bbox: Aabb::enclosing(
+                &Aabb::from_points(q, q + u + v),
+                &Aabb::from_points(q + u, q + v),
+            ),
+        }
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        let denom = self.normal.dot(r.direction());
+
+        // Parallel to the plane
+        if denom.abs() < 1e-8 {
+            return false;
+        }
+
+        let t = (self.d - self.normal.dot(r.origin())) / denom;
+        if !ray_t.surrounds(t) {
+            return false;
+        }
+
+        // Planar coordinates of the hit along the edges, inside the quad both lie in [0, 1]
+        let intersection = r.at(t);
+        let planar_hitpt = intersection - self.q;
+        let alpha = self.w.dot(&planar_hitpt.cross(&self.v));
+        let beta = self.w.dot(&self.u.cross(&planar_hitpt));
+
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = intersection;
+        rec.u = alpha;
+        rec.v = beta;
+        rec.tangent = self.u;
+        rec.set_face_normal(r, &self.normal);
+        rec.mat = Arc::clone(&self.mat);
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    // Uniform over the area, converted to solid angle as seen from `origin`
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f32 {
+        let mut rec = HitRecord::default();
+        if !self.hit(
+            &Ray::new(*origin, *direction, 0.0),
+            Interval::new(0.001, f32::INFINITY),
+            &mut rec,
+        ) {
+            return 0.0;
+        }
+
+        let distance_squared = rec.t * rec.t * direction.dot(direction);
+        let cosine = (direction.dot(&rec.normal) / direction.length()).abs();
+        distance_squared / (cosine * self.area)
+    }
+
+    fn random(&self, origin: &Point3, sampler: &mut Sampler) -> Vec3 {
+        let p =
+            self.q + self.u.scale(sampler.random_float()) + self.v.scale(sampler.random_float());
+        p - *origin
+    }
+}
+
+/// Closed axis-aligned box with opposite corners `a` and `b`, built from six outward facing quads
+pub fn make_box(a: Point3, b: Point3, mat: Arc<dyn Material>) -> HittableList {
+    let mut sides = HittableList::new();
+
+    let min = Point3::new(a.x().min(b.x()), a.y().min(b.y()), a.z().min(b.z()));
+    let max = Point3::new(a.x().max(b.x()), a.y().max(b.y()), a.z().max(b.z()));
+
+    let dx = Vec3::new(max.x() - min.x(), 0.0, 0.0);
+    let dy = Vec3::new(0.0, max.y() - min.y(), 0.0);
+    let dz = Vec3::new(0.0, 0.0, max.z() - min.z());
+
+    let faces = [
+        // Front, right, back, left, top, bottom
+        (Point3::new(min.x(), min.y(), max.z()), dx, dy),
+        (Point3::new(max.x(), min.y(), max.z()), dz.scale(-1.0), dy),
+        (Point3::new(max.x(), min.y(), min.z()), dx.scale(-1.0), dy),
+        (Point3::new(min.x(), min.y(), min.z()), dz, dy),
+        (Point3::new(min.x(), max.y(), max.z()), dx, dz.scale(-1.0)),
+        (Point3::new(min.x(), min.y(), min.z()), dx, dz),
+    ];
+    for (q, u, v) in faces {
+        sides.add(Box::new(Quad::new(q, u, v, Arc::clone(&mat))));
+    }
+
+    sides
+}
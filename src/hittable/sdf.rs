@@ -0,0 +1,323 @@
+use std::sync::Arc;
+
+use crate::{utils::interval::Interval, Hittable, Point3, Ray, Vec3};
+
+use super::{aabb::Aabb, material::Material, sphere::Sphere, HitRecord};
+
+/// Node of a signed distance function tree, negative inside the shape and positive outside
+pub enum SdfNode {
+    Sphere {
+        radius: f32,
+    },
+    /// Box around the origin with corners rounded off by `radius`, which adds to its size
+    RoundedBox {
+        half_size: Vec3,
+        radius: f32,
+    },
+    /// Ring around the y axis
+    Torus {
+        major_radius: f32,
+        minor_radius: f32,
+    },
+    /// Power `power` Mandelbulb fractal around the origin, more `iterations` bring out finer
+    /// detail
+    Mandelbulb {
+        power: f32,
+        iterations: usize,
+    },
+    Translate {
+        offset: Vec3,
+        shape: Box<SdfNode>,
+    },
+    /// Union that fills in the creases where `a` and `b` meet, over about `k` units
+    SmoothUnion {
+        a: Box<SdfNode>,
+        b: Box<SdfNode>,
+        k: f32,
+    },
+    /// Rotation about the y axis that grows by `rate` radians per unit of height
+    Twist {
+        rate: f32,
+        shape: Box<SdfNode>,
+    },
+    /// `count` copies along each axis `spacing` apart, centred on the origin. The shape must
+    /// fit inside one cell for distances to stay right
+    Repeat {
+        spacing: Vec3,
+        count: [usize; 3],
+        shape: Box<SdfNode>,
+    },
+}
+
+impl SdfNode {
+    pub fn distance(&self, p: &Point3) -> f32 {
+        match self {
+            Self::Sphere { radius } => p.length() - radius,
+            Self::RoundedBox { half_size, radius } => {
+                let q = Vec3::new(
+                    p.x().abs() - half_size.x(),
+                    p.y().abs() - half_size.y(),
+                    p.z().abs() - half_size.z(),
+                );
+                let outside = Vec3::new(q.x().max(0.0), q.y().max(0.0), q.z().max(0.0));
+                outside.length() + q.x().max(q.y()).max(q.z()).min(0.0) - radius
+            }
+            Self::Torus {
+                major_radius,
+                minor_radius,
+            } => {
+                let ring = (p.x() * p.x() + p.z() * p.z()).sqrt() - major_radius;
+                (ring * ring + p.y() * p.y()).sqrt() - minor_radius
+            }
+            Self::Mandelbulb { power, iterations } => mandelbulb(p, *power, *iterations),
+            Self::Translate { offset, shape } => shape.distance(&(*p - *offset)),
+            Self::SmoothUnion { a, b, k } => {
+                let (da, db) = (a.distance(p), b.distance(p));
+                if *k <= 0.0 {
+                    return da.min(db);
+                }
+                // Polynomial smooth minimum of Quilez
+                let h = (k - (da - db).abs()).max(0.0) / k;
+                da.min(db) - h * h * k / 4.0
+            }
+            Self::Twist { rate, shape } => {
+                let (sin, cos) = (rate * p.y()).sin_cos();
+                let q = Point3::new(cos * p.x() - sin * p.z(), p.y(), sin * p.x() + cos * p.z());
+                shape.distance(&q)
+            }
+            Self::Repeat {
+                spacing,
+                count,
+                shape,
+            } => {
+                // The nearest cell, clamped to the copies that exist
+                let local = |x: f32, s: f32, n: usize| {
+                    let half = 0.5 * (n as f32 - 1.0) * s;
+                    let cell = ((x + half) / s).round().clamp(0.0, n as f32 - 1.0);
+                    x + half - cell * s
+                };
+                let q = Point3::new(
+                    local(p.x(), spacing.x(), count[0]),
+                    local(p.y(), spacing.y(), count[1]),
+                    local(p.z(), spacing.z(), count[2]),
+                );
+                shape.distance(&q)
+            }
+        }
+    }
+
+    /// Box holding every point where the distance is negative
+    fn bounds(&self) -> Aabb {
+        let centred = |half: Vec3| Aabb::from_points(half.scale(-1.0), half);
+        match self {
+            Self::Sphere { radius } => centred(Vec3::new(*radius, *radius, *radius)),
+            Self::RoundedBox { half_size, radius } => {
+                centred(*half_size + Vec3::new(*radius, *radius, *radius))
+            }
+            Self::Torus {
+                major_radius,
+                minor_radius,
+            } => {
+                let outer = major_radius + minor_radius;
+                centred(Vec3::new(outer, *minor_radius, outer))
+            }
+            // Points further than 2 from the origin escape under any power of at least 2
+            Self::Mandelbulb { .. } => centred(Vec3::new(2.0, 2.0, 2.0)),
+            Self::Translate { offset, shape } => {
+                let inner = shape.bounds();
+                Aabb::new(
+                    Interval::new(inner.x.min + offset.x(), inner.x.max + offset.x()),
+                    Interval::new(inner.y.min + offset.y(), inner.y.max + offset.y()),
+                    Interval::new(inner.z.min + offset.z(), inner.z.max + offset.z()),
+                )
+            }
+            Self::SmoothUnion { a, b, k } => {
+                // The blend swells the surface by at most a quarter of k
+                let union = Aabb::enclosing(&a.bounds(), &b.bounds());
+                let pad = k.max(0.0) / 2.0;
+                Aabb::new(
+                    union.x.expand(pad),
+                    union.y.expand(pad),
+                    union.z.expand(pad),
+                )
+            }
+            Self::Twist { shape, .. } => {
+                let inner = shape.bounds();
+                let reach = radial_reach(&inner);
+                Aabb::new(
+                    Interval::new(-reach, reach),
+                    inner.y,
+                    Interval::new(-reach, reach),
+                )
+            }
+            Self::Repeat {
+                spacing,
+                count,
+                shape,
+            } => {
+                let inner = shape.bounds();
+                let spread = |axis: usize| 0.5 * (count[axis] as f32 - 1.0) * spacing[axis].abs();
+                let grow = |i: &Interval, axis: usize| {
+                    Interval::new(i.min - spread(axis), i.max + spread(axis))
+                };
+                Aabb::new(grow(&inner.x, 0), grow(&inner.y, 1), grow(&inner.z, 2))
+            }
+        }
+    }
+
+    /// Upper bound on how much faster than the true distance the function can change, which
+    /// steps are divided by so they never pass through the surface
+    fn lipschitz(&self) -> f32 {
+        match self {
+            Self::Translate { shape, .. } | Self::Repeat { shape, .. } => shape.lipschitz(),
+            Self::SmoothUnion { a, b, .. } => a.lipschitz().max(b.lipschitz()),
+            Self::Twist { rate, shape } => {
+                // Largest singular value of the twist's Jacobian at the furthest point from the
+                // axis that matters
+                let a = rate.abs() * radial_reach(&shape.bounds());
+                shape.lipschitz() * ((4.0 + a * a).sqrt() + a) / 2.0
+            }
+            _ => 1.0,
+        }
+    }
+}
+
+/// Furthest any point of `bbox` lies from the y axis
+fn radial_reach(bbox: &Aabb) -> f32 {
+    let x = bbox.x.min.abs().max(bbox.x.max.abs());
+    let z = bbox.z.min.abs().max(bbox.z.max.abs());
+    (x * x + z * z).sqrt()
+}
+
+/// Distance estimate of the Mandelbulb from the running derivative of its iteration
+fn mandelbulb(p: &Point3, power: f32, iterations: usize) -> f32 {
+    let mut z = *p;
+    let mut dr = 1.0;
+    let mut r = z.length();
+    for _ in 0..iterations {
+        if r > 2.0 {
+            break;
+        }
+        // Raise z to `power` in spherical coordinates about the y axis
+        let theta = (z.y() / r).clamp(-1.0, 1.0).acos() * power;
+        let phi = z.z().atan2(z.x()) * power;
+        dr = power * r.powf(power - 1.0) * dr + 1.0;
+        let zr = r.powf(power);
+        z = Point3::new(
+            theta.sin() * phi.cos(),
+            theta.cos(),
+            theta.sin() * phi.sin(),
+        )
+        .scale(zr)
+            + *p;
+        r = z.length();
+    }
+    if r < 1e-6 {
+        return 0.0;
+    }
+    0.5 * r.ln() * r / dr
+}
+
+/// Shape described by a signed distance function, rendered by sphere tracing
+///
+/// Every step along a ray moves as far as the distance function guarantees is empty, until
+/// the ray is close enough to call it a hit or leaves the bounding box. Normals come from the
+/// gradient of the distance and surface coordinates from the normal, as for a sphere, so any
+/// material works on it.
+pub struct Sdf {
+    root: SdfNode,
+    mat: Arc<dyn Material>,
+    /// Reciprocal of the Lipschitz bound of `root`
+    step_scale: f32,
+    bbox: Aabb,
+}
+
+impl Sdf {
+    const MAX_STEPS: usize = 512;
+    /// Distance that counts as touching the surface
+    const EPSILON: f32 = 1e-4;
+
+    pub fn new(root: SdfNode, mat: Arc<dyn Material>) -> Self {
+        Self {
+            step_scale: 1.0 / root.lipschitz(),
+            bbox: root.bounds(),
+            root,
+            mat,
+        }
+    }
+
+    /// Unit gradient of the distance from four samples on a tetrahedron around `p`
+    fn normal(&self, p: &Point3) -> Vec3 {
+        let h = Self::EPSILON;
+        [
+            Vec3::new(1.0, -1.0, -1.0),
+            Vec3::new(-1.0, -1.0, 1.0),
+            Vec3::new(-1.0, 1.0, -1.0),
+            Vec3::new(1.0, 1.0, 1.0),
+        ]
+        .iter()
+        .fold(Vec3::default(), |sum, k| {
+            sum + k.scale(self.root.distance(&(*p + k.scale(h))))
+        })
+        .unit_vector()
+    }
+}
+
+impl Hittable for Sdf {
+    fn hit(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        let Some(span) = self.bbox.clip(r, ray_t) else {
+            return false;
+        };
+        let speed = r.direction().length();
+
+        // A ray starting on the surface, such as a shadow ray or the search for the next
+        // crossing from the last, first moves clear of it so the march finds the crossing
+        // after the one it starts on. Steps double to get through grazing starts quickly
+        let mut t = span.min;
+        let mut distance = self.root.distance(&r.at(t));
+        if !ray_t.surrounds(t) {
+            let mut nudge = Self::EPSILON;
+            while distance.abs() < Self::EPSILON {
+                t += nudge / speed;
+                nudge *= 2.0;
+                if t >= span.max {
+                    return false;
+                }
+                distance = self.root.distance(&r.at(t));
+            }
+        }
+
+        // Rays starting inside, such as through glass, look for where the distance turns
+        // positive instead
+        let side = distance.signum();
+        let mut hit = false;
+        for _ in 0..Self::MAX_STEPS {
+            let d = side * self.root.distance(&r.at(t)) * self.step_scale;
+            if d < Self::EPSILON {
+                hit = true;
+                break;
+            }
+            if t >= span.max {
+                break;
+            }
+            // Surfaces can touch the bounding box, so the last sample is taken on it
+            t = (t + d / speed).min(span.max);
+        }
+        if !hit || !ray_t.surrounds(t) {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = r.at(t);
+        let outward_normal = self.normal(&rec.p);
+        rec.set_face_normal(r, &outward_normal);
+        (rec.u, rec.v) = Sphere::get_sphere_uv(&outward_normal);
+        rec.tangent = Sphere::get_sphere_tangent(&outward_normal);
+        rec.mat = Arc::clone(&self.mat);
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
@@ -1,32 +1,70 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
-use crate::utils::interval::Interval;
+use crate::utils::{interval::Interval, onb::Onb, sampler::Sampler};
 use crate::Hittable;
 use crate::Point3;
 use crate::Ray;
+use crate::Vec3;
 
+use super::aabb::Aabb;
 use super::material::Material;
 use super::HitRecord;
 
 pub struct Sphere {
-    center: Point3,
+    /// Centre at time 0 as the origin, moving by the direction until time 1
+    center: Ray,
     radius: f32,
-    mat: Rc<dyn Material>,
+    mat: Arc<dyn Material>,
+    bbox: Aabb,
 }
 
 impl Sphere {
-    pub fn new(center: Point3, radius: f32, mat: Rc<dyn Material>) -> Self {
+    pub fn new(center: Point3, radius: f32, mat: Arc<dyn Material>) -> Self {
+        Self::moving(center, center, radius, mat)
+    }
+
+    /// Sphere moving in a straight line from `center0` at time 0 to `center1` at time 1,
+    /// resting at either end outside that range so its bounding box covers every position
+    pub fn moving(center0: Point3, center1: Point3, radius: f32, mat: Arc<dyn Material>) -> Self {
+        let rvec = Vec3::new(radius, radius, radius);
+        let box0 = Aabb::from_points(center0 - rvec, center0 + rvec);
+        let box1 = Aabb::from_points(center1 - rvec, center1 + rvec);
         Sphere {
-            center,
+            center: Ray::new(center0, center1 - center0, 0.0),
             radius,
             mat,
+            bbox: Aabb::enclosing(&box0, &box1),
         }
     }
+
+    fn center_at(&self, time: f32) -> Point3 {
+        self.center.at(time.clamp(0.0, 1.0))
+    }
+}
+
+impl Sphere {
+    /// Surface coordinates of a point on the unit sphere, u follows longitude from -x through +z
+    /// and v latitude from the south pole to the north pole
+    pub(super) fn get_sphere_uv(p: &Point3) -> (f32, f32) {
+        let theta = (-p.y()).acos();
+        let phi = (-p.z()).atan2(p.x()) + std::f32::consts::PI;
+
+        (
+            phi / (2.0 * std::f32::consts::PI),
+            theta / std::f32::consts::PI,
+        )
+    }
+
+    /// Direction of growing u at a point on the unit sphere, along the line of latitude
+    pub(super) fn get_sphere_tangent(p: &Point3) -> Vec3 {
+        Vec3::new(p.z(), 0.0, -p.x())
+    }
 }
 
 impl Hittable for Sphere {
-    fn hit(&mut self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
-        let oc = *r.origin() - self.center;
+    fn hit(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        let center = self.center_at(r.time());
+        let oc = *r.origin() - center;
         let a = r.direction().length() * r.direction().length();
         let half_b = oc.dot(r.direction());
         let c = oc.length() * oc.length() - self.radius * self.radius;
@@ -50,9 +88,74 @@ impl Hittable for Sphere {
         rec.t = root;
         rec.p = r.at(rec.t);
 
-        let outward_normal = (rec.p - self.center).scale(1.0 / self.radius);
+        let outward_normal = (rec.p - center).scale(1.0 / self.radius);
         rec.set_face_normal(r, &outward_normal);
-        rec.mat = Rc::clone(&self.mat);
-        return true;
+        (rec.u, rec.v) = Self::get_sphere_uv(&outward_normal);
+        rec.tangent = Self::get_sphere_tangent(&outward_normal);
+        rec.mat = Arc::clone(&self.mat);
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    // Uniform over the cone the sphere subtends, which leaves nothing to sample from inside it.
+    // Directions carry no time, so only spheres that stay put are sampled as lights
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f32 {
+        let center = self.center_at(0.0);
+        let mut rec = HitRecord::default();
+        if !self.hit(
+            &Ray::new(*origin, *direction, 0.0),
+            Interval::new(0.001, f32::INFINITY),
+            &mut rec,
+        ) {
+            return 0.0;
+        }
+
+        let distance_squared = (center - *origin).dot(&(center - *origin));
+        if distance_squared <= self.radius * self.radius {
+            return 0.0;
+        }
+
+        let cos_theta_max = (1.0 - self.radius * self.radius / distance_squared).sqrt();
+        let solid_angle = 2.0 * std::f32::consts::PI * (1.0 - cos_theta_max);
+        1.0 / solid_angle
+    }
+
+    fn random(&self, origin: &Point3, sampler: &mut Sampler) -> Vec3 {
+        let direction = self.center_at(0.0) - *origin;
+        let distance_squared = direction.dot(&direction);
+        let uvw = Onb::new(&direction);
+
+        uvw.transform(&Vec3::random_to_sphere(
+            sampler,
+            self.radius,
+            distance_squared,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tangent_points_along_growing_u() {
+        for p in [
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(0.0, 0.6, 0.8),
+            Point3::new(-0.48, -0.6, 0.64),
+            Point3::new(0.36, 0.8, -0.48),
+        ] {
+            let tangent = Sphere::get_sphere_tangent(&p);
+            assert!(tangent.dot(&p).abs() < 1e-6);
+
+            let step = 1e-3;
+            let (u0, _) = Sphere::get_sphere_uv(&p);
+            let (u1, _) = Sphere::get_sphere_uv(&(p + tangent.scale(step)).unit_vector());
+            let du = (u1 - u0).rem_euclid(1.0);
+            assert!(du > 0.0 && du < 0.01, "{p:?}: {u0} -> {u1}");
+        }
     }
 }
@@ -0,0 +1,11 @@
+pub mod checker;
+pub mod image;
+pub mod noise;
+pub mod solid_colour;
+
+use crate::{Colour, Point3};
+
+/// Colour that varies over a surface, looked up by surface coordinates and hit point
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f32, v: f32, p: &Point3) -> Colour;
+}
@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use crate::{Colour, Point3};
+
+use super::Texture;
+
+/// Solid 3D checker pattern of cubes with edge length `scale`
+pub struct CheckerTexture {
+    inv_scale: f32,
+    even: Arc<dyn Texture>,
+    odd: Arc<dyn Texture>,
+}
+
+impl CheckerTexture {
+    pub fn new(scale: f32, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> Self {
+        Self {
+            inv_scale: 1.0 / scale,
+            even,
+            odd,
+        }
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f32, v: f32, p: &Point3) -> Colour {
+        let cell = |x: f32| (self.inv_scale * x).floor() as i64;
+        let is_even = (cell(p.x()) + cell(p.y()) + cell(p.z())) % 2 == 0;
+
+        if is_even {
+            self.even.value(u, v, p)
+        } else {
+            self.odd.value(u, v, p)
+        }
+    }
+}
@@ -0,0 +1,285 @@
+mod exr;
+mod hdr;
+
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::{Colour, Point3};
+
+use super::Texture;
+
+/// How lookups outside [0, 1] map back onto the image
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WrapMode {
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+impl WrapMode {
+    // Maps an integer texel coordinate into [0, size)
+    fn apply(&self, x: i64, size: usize) -> usize {
+        let size = size as i64;
+        let wrapped = match self {
+            Self::Repeat => x.rem_euclid(size),
+            Self::Clamp => x.clamp(0, size - 1),
+            Self::Mirror => {
+                let period = x.rem_euclid(2 * size);
+                if period < size {
+                    period
+                } else {
+                    2 * size - 1 - period
+                }
+            }
+        };
+
+        wrapped as usize
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Filter {
+    Nearest,
+    Bilinear,
+}
+
+/// Decoded image with linear colour texels, stored row by row from the top
+pub struct ImageData {
+    pub width: usize,
+    pub height: usize,
+    pub texels: Vec<Colour>,
+}
+
+impl ImageData {
+    /// Reads a PNG, PPM (P3 or P6), Radiance HDR or OpenEXR file. 8-bit samples are treated as
+    /// gamma 2.2 encoded, HDR and EXR already hold linear values
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("png") => Self::load_png(path),
+            Some("ppm") => Self::load_ppm(path),
+            Some("hdr") => hdr::load(path),
+            Some("exr") => exr::load(path),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unsupported image type {}", path.display()),
+            )),
+        }
+    }
+
+    fn from_rgb8(width: usize, height: usize, rgb: &[u8], max_value: f32) -> Self {
+        let decode = |c: u8| (c as f32 / max_value).powf(2.2);
+        let texels = rgb
+            .chunks_exact(3)
+            .map(|p| Colour::new(decode(p[0]), decode(p[1]), decode(p[2])))
+            .collect();
+
+        Self {
+            width,
+            height,
+            texels,
+        }
+    }
+
+    fn load_png(path: &Path) -> io::Result<Self> {
+        let mut decoder = ::png::Decoder::new(BufReader::new(File::open(path)?));
+        decoder.set_transformations(::png::Transformations::normalize_to_color8());
+
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        let bytes = &buffer[..info.buffer_size()];
+
+        let rgb: Vec<u8> = match info.color_type {
+            ::png::ColorType::Rgb => bytes.to_vec(),
+            ::png::ColorType::Rgba => bytes
+                .chunks_exact(4)
+                .flat_map(|p| [p[0], p[1], p[2]])
+                .collect(),
+            ::png::ColorType::Grayscale => bytes.iter().flat_map(|&g| [g, g, g]).collect(),
+            ::png::ColorType::GrayscaleAlpha => bytes
+                .chunks_exact(2)
+                .flat_map(|p| [p[0], p[0], p[0]])
+                .collect(),
+            ::png::ColorType::Indexed => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "indexed PNG was not expanded",
+                ))
+            }
+        };
+
+        Ok(Self::from_rgb8(
+            info.width as usize,
+            info.height as usize,
+            &rgb,
+            255.0,
+        ))
+    }
+
+    fn load_ppm(path: &Path) -> io::Result<Self> {
+        Self::decode_ppm(&fs::read(path)?)
+    }
+
+    fn decode_ppm(bytes: &[u8]) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        // Header fields are whitespace separated and may be followed by comments
+        let mut pos = 0;
+        let mut next_token = || -> Option<String> {
+            loop {
+                while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+                    pos += 1;
+                }
+                if pos < bytes.len() && bytes[pos] == b'#' {
+                    while pos < bytes.len() && bytes[pos] != b'\n' {
+                        pos += 1;
+                    }
+                    continue;
+                }
+                break;
+            }
+            let start = pos;
+            while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            (start < pos).then(|| String::from_utf8_lossy(&bytes[start..pos]).into_owned())
+        };
+
+        let magic = next_token().ok_or_else(|| invalid("missing PPM magic"))?;
+        let mut number = || {
+            next_token()
+                .and_then(|t| t.parse::<usize>().ok())
+                .ok_or_else(|| invalid("malformed PPM header"))
+        };
+        let (width, height, max_value) = (number()?, number()?, number()?);
+        if max_value == 0 || max_value > 255 {
+            return Err(invalid("only 8-bit PPM files are supported"));
+        }
+
+        let samples = width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(3))
+            .ok_or_else(|| invalid("PPM image too large"))?;
+
+        let rgb: Vec<u8> = match magic.as_str() {
+            "P6" => {
+                // Exactly one whitespace byte separates the header from the samples
+                bytes
+                    .get(pos + 1..)
+                    .and_then(|data| data.get(..samples))
+                    .ok_or_else(|| invalid("truncated PPM data"))?
+                    .to_vec()
+            }
+            "P3" => (0..samples)
+                .map(|_| {
+                    next_token()
+                        .and_then(|t| t.parse::<u8>().ok())
+                        .ok_or_else(|| invalid("truncated PPM data"))
+                })
+                .collect::<io::Result<_>>()?,
+            _ => return Err(invalid("not a P3 or P6 PPM file")),
+        };
+
+        Ok(Self::from_rgb8(width, height, &rgb, max_value as f32))
+    }
+
+    fn texel(&self, x: i64, y: i64, wrap: WrapMode) -> Colour {
+        let i = wrap.apply(x, self.width);
+        let j = wrap.apply(y, self.height);
+        self.texels[j * self.width + i]
+    }
+}
+
+pub struct ImageTexture {
+    image: ImageData,
+    wrap: WrapMode,
+    filter: Filter,
+}
+
+impl ImageTexture {
+    pub fn new(image: ImageData, wrap: WrapMode, filter: Filter) -> Self {
+        Self {
+            image,
+            wrap,
+            filter,
+        }
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f32, v: f32, _p: &Point3) -> Colour {
+        if self.image.texels.is_empty() {
+            // Cyan makes missing textures easy to spot
+            return Colour::new(0.0, 1.0, 1.0);
+        }
+
+        // Image rows run top to bottom while v runs bottom to top
+        let x = u * self.image.width as f32;
+        let y = (1.0 - v) * self.image.height as f32;
+
+        match self.filter {
+            Filter::Nearest => self
+                .image
+                .texel(x.floor() as i64, y.floor() as i64, self.wrap),
+            Filter::Bilinear => {
+                // Texel centres sit at half-integer coordinates
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (x0, y0) = (x.floor(), y.floor());
+                let (tx, ty) = (x - x0, y - y0);
+                let (i, j) = (x0 as i64, y0 as i64);
+
+                let top = self.image.texel(i, j, self.wrap).scale(1.0 - tx)
+                    + self.image.texel(i + 1, j, self.wrap).scale(tx);
+                let bottom = self.image.texel(i, j + 1, self.wrap).scale(1.0 - tx)
+                    + self.image.texel(i + 1, j + 1, self.wrap).scale(tx);
+
+                top.scale(1.0 - ty) + bottom.scale(ty)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_invalid(bytes: &[u8]) {
+        let error = ImageData::decode_ppm(bytes)
+            .err()
+            .expect("malformed file was read");
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn reads_plain_and_binary_ppm() {
+        let plain = ImageData::decode_ppm(b"P3\n# comment\n2 1\n255\n255 0 0  0 0 255\n").unwrap();
+        let mut binary = b"P6 2 1 255\n".to_vec();
+        binary.extend([255, 0, 0, 0, 0, 255]);
+        let binary = ImageData::decode_ppm(&binary).unwrap();
+
+        for image in [plain, binary] {
+            assert_eq!((image.width, image.height), (2, 1));
+            assert!(image.texels[0].x() > 0.99 && image.texels[0].z() == 0.0);
+            assert!(image.texels[1].z() > 0.99 && image.texels[1].x() == 0.0);
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_and_oversized_files() {
+        assert_invalid(b"P6 2 2 255\n\x00\x00\x00");
+        assert_invalid(b"P3 2 1 255\n1 2 3 4 5");
+        assert_invalid(b"P6 4294967296 4294967296 255\n");
+        assert_invalid(b"P6 18446744073709551615 1 255\n");
+        assert_invalid(b"P3 6148914691236517206 1 255\n1 2 3");
+    }
+}
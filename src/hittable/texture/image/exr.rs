@@ -0,0 +1,379 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use flate2::read::ZlibDecoder;
+
+use crate::Colour;
+
+use super::ImageData;
+
+const MAGIC: u32 = 20000630;
+// Tiled, deep and multi-part files use layouts this reader does not handle
+const UNSUPPORTED_FLAGS: u32 = 0x200 | 0x800 | 0x1000;
+
+const PIXEL_TYPE_UINT: i32 = 0;
+const PIXEL_TYPE_HALF: i32 = 1;
+
+struct Channel {
+    name: String,
+    pixel_type: i32,
+}
+
+impl Channel {
+    fn bytes(&self) -> usize {
+        if self.pixel_type == PIXEL_TYPE_HALF {
+            2
+        } else {
+            4
+        }
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn read_i32(bytes: &[u8], pos: usize) -> io::Result<i32> {
+    pos.checked_add(4)
+        .and_then(|end| bytes.get(pos..end))
+        .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| invalid("truncated EXR file"))
+}
+
+fn read_cstr(bytes: &[u8], pos: &mut usize) -> io::Result<String> {
+    let len = bytes[*pos..]
+        .iter()
+        .position(|&b| b == 0)
+        .ok_or_else(|| invalid("truncated EXR header"))?;
+    let s = String::from_utf8_lossy(&bytes[*pos..*pos + len]).into_owned();
+    *pos += len + 1;
+    Ok(s)
+}
+
+fn parse_channels(value: &[u8]) -> io::Result<Vec<Channel>> {
+    let mut channels = Vec::new();
+    let mut pos = 0;
+    while value.get(pos).is_some_and(|&b| b != 0) {
+        let name = read_cstr(value, &mut pos)?;
+        let pixel_type = read_i32(value, pos)?;
+        let (x_sampling, y_sampling) = (read_i32(value, pos + 8)?, read_i32(value, pos + 12)?);
+        if x_sampling != 1 || y_sampling != 1 {
+            return Err(invalid("subsampled EXR channels are not supported"));
+        }
+        channels.push(Channel { name, pixel_type });
+        pos += 16;
+    }
+    Ok(channels)
+}
+
+/// Reads a single-part scanline OpenEXR file with NONE, RLE, ZIPS or ZIP compression
+///
+/// R, G and B are used when present, otherwise a Y channel is read as grey.
+pub fn load(path: &Path) -> io::Result<ImageData> {
+    decode(&fs::read(path)?)
+}
+
+fn decode(bytes: &[u8]) -> io::Result<ImageData> {
+    if read_i32(bytes, 0)? as u32 != MAGIC {
+        return Err(invalid("not an OpenEXR file"));
+    }
+    if read_i32(bytes, 4)? as u32 & UNSUPPORTED_FLAGS != 0 {
+        return Err(invalid("only single-part scanline EXR files are supported"));
+    }
+
+    let mut attributes = HashMap::new();
+    let mut pos = 8;
+    while bytes.get(pos).is_some_and(|&b| b != 0) {
+        let name = read_cstr(bytes, &mut pos)?;
+        let _kind = read_cstr(bytes, &mut pos)?;
+        let size = usize::try_from(read_i32(bytes, pos)?)
+            .map_err(|_| invalid("negative EXR attribute size"))?;
+        let end = (pos + 4)
+            .checked_add(size)
+            .ok_or_else(|| invalid("truncated EXR header"))?;
+        let value = bytes
+            .get(pos + 4..end)
+            .ok_or_else(|| invalid("truncated EXR header"))?;
+        attributes.insert(name, value);
+        pos = end;
+    }
+    pos += 1;
+
+    let attribute = |name: &str| {
+        attributes
+            .get(name)
+            .copied()
+            .ok_or_else(|| invalid(&format!("EXR header has no {}", name)))
+    };
+    let channels = parse_channels(attribute("channels")?)?;
+    let compression = *attribute("compression")?
+        .first()
+        .ok_or_else(|| invalid("empty compression attribute"))?;
+    let window = attribute("dataWindow")?;
+    let (x_min, y_min, x_max, y_max) = (
+        read_i32(window, 0)?,
+        read_i32(window, 4)?,
+        read_i32(window, 8)?,
+        read_i32(window, 12)?,
+    );
+    if x_max < x_min || y_max < y_min {
+        return Err(invalid("empty EXR data window"));
+    }
+    // Computed in i64 so windows spanning most of the i32 range do not overflow
+    let width = usize::try_from(x_max as i64 - x_min as i64 + 1)
+        .map_err(|_| invalid("EXR data window too large"))?;
+    let height = usize::try_from(y_max as i64 - y_min as i64 + 1)
+        .map_err(|_| invalid("EXR data window too large"))?;
+
+    // Most any compression can grow data by, which bounds how many pixels the rest of the
+    // file can hold: 128 bytes from a two byte RLE run, or deflate's limit of about 1032:1
+    let (lines_per_chunk, max_expansion) = match compression {
+        0 => (1, 1),
+        1 => (1, 64),
+        2 => (1, 1032),
+        3 => (16, 1032),
+        _ => return Err(invalid("unsupported EXR compression")),
+    };
+
+    let find = |name: &str| channels.iter().position(|c| c.name == name);
+    let sources = match (find("R"), find("G"), find("B"), find("Y")) {
+        (Some(r), Some(g), Some(b), _) => [r, g, b],
+        (_, _, _, Some(y)) => [y, y, y],
+        _ => return Err(invalid("EXR file has no RGB or Y channels")),
+    };
+
+    let pixel_bytes: usize = channels.iter().map(Channel::bytes).sum();
+    let line_bytes = width
+        .checked_mul(pixel_bytes)
+        .ok_or_else(|| invalid("EXR data window too large"))?;
+    let holds = bytes
+        .len()
+        .saturating_sub(pos)
+        .saturating_mul(max_expansion);
+    if line_bytes
+        .checked_mul(height)
+        .is_none_or(|total| total > holds)
+    {
+        return Err(invalid("EXR data window is larger than the file holds"));
+    }
+    let chunks = height.div_ceil(lines_per_chunk);
+    let mut texels = vec![Colour::default(); width * height];
+
+    for chunk in 0..chunks {
+        let offset = bytes
+            .get(pos + 8 * chunk..pos + 8 * chunk + 8)
+            .and_then(|b| usize::try_from(u64::from_le_bytes(b.try_into().unwrap())).ok())
+            .ok_or_else(|| invalid("truncated EXR offset table"))?;
+        let y = read_i32(bytes, offset)?;
+        let size = usize::try_from(read_i32(bytes, offset + 4)?)
+            .map_err(|_| invalid("negative EXR chunk size"))?;
+        let packed = (offset + 8)
+            .checked_add(size)
+            .and_then(|end| bytes.get(offset + 8..end))
+            .ok_or_else(|| invalid("truncated EXR chunk"))?;
+
+        let y = usize::try_from(y as i64 - y_min as i64)
+            .ok()
+            .filter(|&y| y < height)
+            .ok_or_else(|| invalid("EXR chunk outside the data window"))?;
+        let lines = lines_per_chunk.min(height - y);
+        let expected = lines * line_bytes;
+
+        // Chunks that would not shrink are stored raw whatever the compression
+        let raw = if compression == 0 || size == expected {
+            packed.to_vec()
+        } else {
+            let mut encoded = Vec::with_capacity(expected);
+            if compression == 1 {
+                decode_rle(packed, &mut encoded)?;
+            } else {
+                // One byte past the expected size is enough to tell the chunk is wrong
+                ZlibDecoder::new(packed)
+                    .take(expected as u64 + 1)
+                    .read_to_end(&mut encoded)?;
+            }
+            unpredict(&encoded)
+        };
+        if raw.len() != expected {
+            return Err(invalid("EXR chunk has the wrong size"));
+        }
+
+        for (line, data) in raw.chunks_exact(line_bytes).enumerate() {
+            // Within a line each channel's samples are stored together, in header order
+            let mut starts = Vec::with_capacity(channels.len());
+            let mut start = 0;
+            for channel in &channels {
+                starts.push(start);
+                start += channel.bytes() * width;
+            }
+
+            let sample = |c: usize, x: usize| -> f32 {
+                let channel = &channels[c];
+                let at = starts[c] + x * channel.bytes();
+                match channel.pixel_type {
+                    PIXEL_TYPE_HALF => half_to_f32(u16::from_le_bytes([data[at], data[at + 1]])),
+                    PIXEL_TYPE_UINT => {
+                        u32::from_le_bytes(data[at..at + 4].try_into().unwrap()) as f32
+                    }
+                    _ => f32::from_le_bytes(data[at..at + 4].try_into().unwrap()),
+                }
+            };
+
+            let row = &mut texels[(y + line) * width..(y + line + 1) * width];
+            for (x, texel) in row.iter_mut().enumerate() {
+                *texel = Colour::new(
+                    sample(sources[0], x),
+                    sample(sources[1], x),
+                    sample(sources[2], x),
+                );
+            }
+        }
+    }
+
+    Ok(ImageData {
+        width,
+        height,
+        texels,
+    })
+}
+
+fn decode_rle(packed: &[u8], out: &mut Vec<u8>) -> io::Result<()> {
+    let mut pos = 0;
+    while pos < packed.len() {
+        let count = packed[pos] as i8;
+        pos += 1;
+        if count < 0 {
+            let literal = packed
+                .get(pos..pos + (-(count as i32)) as usize)
+                .ok_or_else(|| invalid("truncated RLE data"))?;
+            out.extend_from_slice(literal);
+            pos += literal.len();
+        } else {
+            let value = *packed
+                .get(pos)
+                .ok_or_else(|| invalid("truncated RLE data"))?;
+            out.extend(std::iter::repeat_n(value, count as usize + 1));
+            pos += 1;
+        }
+    }
+    Ok(())
+}
+
+// Undoes the delta predictor and the split into even and odd bytes that RLE and ZIP share
+fn unpredict(encoded: &[u8]) -> Vec<u8> {
+    let mut deltas = encoded.to_vec();
+    for i in 1..deltas.len() {
+        deltas[i] = deltas[i - 1].wrapping_add(deltas[i]).wrapping_sub(128);
+    }
+
+    let half = deltas.len().div_ceil(2);
+    let mut raw = vec![0; deltas.len()];
+    for (i, byte) in raw.iter_mut().enumerate() {
+        *byte = if i % 2 == 0 {
+            deltas[i / 2]
+        } else {
+            deltas[half + i / 2]
+        };
+    }
+    raw
+}
+
+fn half_to_f32(h: u16) -> f32 {
+    let sign = if h & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((h >> 10) & 0x1f) as i32;
+    let mantissa = (h & 0x3ff) as f32;
+
+    match exponent {
+        0 => sign * mantissa * 2f32.powi(-24),
+        31 if mantissa == 0.0 => sign * f32::INFINITY,
+        31 => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        output::{
+            exr::{ExrCompression, ExrWriter},
+            ImageWriter,
+        },
+        utils::framebuffer::Framebuffer,
+    };
+
+    use super::*;
+
+    fn encode(width: usize, height: usize, compression: ExrCompression) -> (Framebuffer, Vec<u8>) {
+        let mut image = Framebuffer::new(width, height);
+        for (i, pixel) in image.pixels.iter_mut().enumerate() {
+            let i = i as f32;
+            *pixel = Colour::new(i * 0.25, 1.0 / (i + 1.0), 100.0 - i);
+        }
+        let mut bytes = Vec::new();
+        ExrWriter { compression }.write(&image, &mut bytes).unwrap();
+        (image, bytes)
+    }
+
+    /// Position of the value of the header attribute `name`
+    fn attribute(bytes: &[u8], name: &str) -> usize {
+        let name = format!("{}\0", name);
+        let start = bytes
+            .windows(name.len())
+            .position(|w| w == name.as_bytes())
+            .unwrap();
+        let kind = start + name.len();
+        let size = kind + bytes[kind..].iter().position(|&b| b == 0).unwrap() + 1;
+        size + 4
+    }
+
+    fn assert_invalid(bytes: &[u8]) {
+        let error = decode(bytes).err().expect("malformed file was read");
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn round_trips_writer_output() {
+        for compression in [ExrCompression::None, ExrCompression::Zip] {
+            for (width, height) in [(1, 1), (37, 21)] {
+                let (image, bytes) = encode(width, height, compression);
+                let read = decode(&bytes).unwrap();
+                assert_eq!((read.width, read.height), (width, height));
+                for (a, b) in image.pixels.iter().zip(&read.texels) {
+                    assert_eq!([a.x(), a.y(), a.z()], [b.x(), b.y(), b.z()]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_negative_attribute_size() {
+        let (_, mut bytes) = encode(4, 4, ExrCompression::None);
+        let size = attribute(&bytes, "channels") - 4;
+        bytes[size..size + 4].copy_from_slice(&(-8i32).to_le_bytes());
+        assert_invalid(&bytes);
+    }
+
+    #[test]
+    fn rejects_data_windows_the_file_cannot_hold() {
+        for compression in [ExrCompression::None, ExrCompression::Zip] {
+            for (x_min, x_max, y_max) in [(i32::MIN, i32::MAX, 3i32), (0, 3, 1 << 30)] {
+                let (_, mut bytes) = encode(4, 4, compression);
+                let window = attribute(&bytes, "dataWindow");
+                bytes[window..window + 4].copy_from_slice(&x_min.to_le_bytes());
+                bytes[window + 8..window + 12].copy_from_slice(&x_max.to_le_bytes());
+                bytes[window + 12..window + 16].copy_from_slice(&y_max.to_le_bytes());
+                assert_invalid(&bytes);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let (_, bytes) = encode(8, 8, ExrCompression::Zip);
+        for len in [0, 7, 30, bytes.len() / 2, bytes.len() - 1] {
+            assert!(decode(&bytes[..len]).is_err());
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::Colour;
+
+use super::ImageData;
+
+/// Reads a Radiance RGBE file stored top to bottom, flat or with new-style run-length encoding
+pub fn load(path: &Path) -> io::Result<ImageData> {
+    decode(&fs::read(path)?)
+}
+
+fn decode(bytes: &[u8]) -> io::Result<ImageData> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+    // The header is a block of text lines ended by an empty line, then the resolution line
+    let mut pos = 0;
+    let mut next_line = || -> Option<String> {
+        let end = pos + bytes[pos..].iter().position(|&b| b == b'\n')?;
+        let line = String::from_utf8_lossy(&bytes[pos..end]).into_owned();
+        pos = end + 1;
+        Some(line)
+    };
+
+    let magic = next_line().ok_or_else(|| invalid("missing RGBE header"))?;
+    if !magic.starts_with("#?") {
+        return Err(invalid("not a Radiance HDR file"));
+    }
+    loop {
+        let line = next_line().ok_or_else(|| invalid("unterminated RGBE header"))?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some(format) = line.strip_prefix("FORMAT=") {
+            if format != "32-bit_rle_rgbe" {
+                return Err(invalid("only RGB Radiance files are supported"));
+            }
+        }
+    }
+
+    let resolution = next_line().ok_or_else(|| invalid("missing resolution line"))?;
+    let (height, width) = match resolution.split_whitespace().collect::<Vec<_>>()[..] {
+        ["-Y", height, "+X", width] => (height.parse().ok(), width.parse().ok()),
+        _ => return Err(invalid("only -Y +X oriented HDR files are supported")),
+    };
+    let (height, width): (usize, usize) = height
+        .zip(width)
+        .ok_or_else(|| invalid("malformed resolution line"))?;
+    if width == 0 || height == 0 {
+        return Err(invalid("empty HDR image"));
+    }
+
+    let data = &bytes[pos..];
+
+    // Fewest bytes a scanline can take, four per pixel when flat, or a four byte marker and a
+    // two byte run per 127 pixels of each channel when run-length encoded
+    let encodable = (8..=0x7fff).contains(&width);
+    let smallest_line = if encodable {
+        4 + 8 * width.div_ceil(127)
+    } else {
+        width
+            .checked_mul(4)
+            .ok_or_else(|| invalid("HDR image too large"))?
+    };
+    if smallest_line
+        .checked_mul(height)
+        .is_none_or(|smallest| smallest > data.len())
+    {
+        return Err(invalid("HDR resolution is larger than the file holds"));
+    }
+
+    let mut pos = 0;
+    let mut texels = Vec::with_capacity(width * height);
+    let mut scanline = vec![[0u8; 4]; width];
+
+    for _ in 0..height {
+        let run_length_encoded = encodable
+            && data.get(pos..pos + 2) == Some(&[2, 2])
+            && data.get(pos + 2).is_some_and(|&hi| hi & 0x80 == 0);
+
+        if run_length_encoded {
+            let encoded_width = data
+                .get(pos + 2..pos + 4)
+                .map(|w| ((w[0] as usize) << 8) | w[1] as usize);
+            if encoded_width != Some(width) {
+                return Err(invalid("scanline width does not match the image"));
+            }
+            pos += 4;
+
+            // Each channel is stored separately as runs or literal spans
+            for channel in 0..4 {
+                let mut x = 0;
+                while x < width {
+                    let count = *data.get(pos).ok_or_else(|| invalid("truncated HDR data"))?;
+                    pos += 1;
+                    if count > 128 {
+                        let count = (count - 128) as usize;
+                        let value = *data.get(pos).ok_or_else(|| invalid("truncated HDR data"))?;
+                        pos += 1;
+                        if x + count > width {
+                            return Err(invalid("run overflows the scanline"));
+                        }
+                        for pixel in &mut scanline[x..x + count] {
+                            pixel[channel] = value;
+                        }
+                        x += count;
+                    } else {
+                        let count = count as usize;
+                        let values = data
+                            .get(pos..pos + count)
+                            .ok_or_else(|| invalid("truncated HDR data"))?;
+                        pos += count;
+                        if count == 0 || x + count > width {
+                            return Err(invalid("bad literal span in scanline"));
+                        }
+                        for (pixel, &value) in scanline[x..x + count].iter_mut().zip(values) {
+                            pixel[channel] = value;
+                        }
+                        x += count;
+                    }
+                }
+            }
+        } else {
+            let flat = data
+                .get(pos..pos + 4 * width)
+                .ok_or_else(|| invalid("truncated HDR data"))?;
+            pos += 4 * width;
+            for (pixel, rgbe) in scanline.iter_mut().zip(flat.chunks_exact(4)) {
+                pixel.copy_from_slice(rgbe);
+            }
+        }
+
+        texels.extend(scanline.iter().map(|&rgbe| from_rgbe(rgbe)));
+    }
+
+    Ok(ImageData {
+        width,
+        height,
+        texels,
+    })
+}
+
+fn from_rgbe([r, g, b, e]: [u8; 4]) -> Colour {
+    if e == 0 {
+        return Colour::default();
+    }
+
+    // Mantissas are in 1/256ths, sample at the centre of each bucket
+    let scale = 2f32.powi(e as i32 - 136);
+    Colour::new(
+        (r as f32 + 0.5) * scale,
+        (g as f32 + 0.5) * scale,
+        (b as f32 + 0.5) * scale,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        output::{hdr::HdrWriter, ImageWriter},
+        utils::framebuffer::Framebuffer,
+    };
+
+    use super::*;
+
+    fn assert_invalid(bytes: &[u8]) {
+        let error = decode(bytes).err().expect("malformed file was read");
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn round_trips_writer_output() {
+        // Narrow images are stored flat and wider ones run-length encoded
+        for (width, height) in [(1, 1), (5, 3), (300, 7)] {
+            let mut image = Framebuffer::new(width, height);
+            for (i, pixel) in image.pixels.iter_mut().enumerate() {
+                let i = i as f32;
+                // Runs of equal pixels as well as changing ones
+                *pixel = Colour::new((i / 10.0).floor(), 1.0 / (i + 1.0), 1000.0 + i);
+            }
+            let mut bytes = Vec::new();
+            HdrWriter.write(&image, &mut bytes).unwrap();
+
+            let read = decode(&bytes).unwrap();
+            assert_eq!((read.width, read.height), (width, height));
+            for (a, b) in image.pixels.iter().zip(&read.texels) {
+                // RGBE keeps 8 bits of mantissa relative to the brightest channel
+                let tolerance = a.x().max(a.y()).max(a.z()) / 128.0;
+                for (a, b) in [(a.x(), b.x()), (a.y(), b.y()), (a.z(), b.z())] {
+                    assert!((a - b).abs() <= tolerance, "{} read back as {}", a, b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_resolutions_the_file_cannot_hold() {
+        for resolution in [
+            "-Y 0 +X 4",
+            "-Y 4 +X 4",
+            "-Y 100000 +X 100000",
+            "-Y 1 +X 18446744073709551615",
+            "-Y 18446744073709551615 +X 64",
+        ] {
+            let bytes = format!("#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n{}\n", resolution);
+            assert_invalid(bytes.as_bytes());
+        }
+    }
+}
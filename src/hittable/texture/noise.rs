@@ -0,0 +1,136 @@
+use serde::Deserialize;
+
+use crate::{utils::sampler::Sampler, Colour, Point3, Vec3};
+
+use super::Texture;
+
+const POINT_COUNT: usize = 256;
+
+/// Perlin gradient noise over a lattice of random unit vectors
+pub struct Perlin {
+    gradients: Vec<Vec3>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new(sampler: &mut Sampler) -> Self {
+        let gradients = (0..POINT_COUNT)
+            .map(|_| Vec3::random_range(sampler, -1.0, 1.0).unit_vector())
+            .collect();
+
+        Self {
+            gradients,
+            perm_x: Self::generate_perm(sampler),
+            perm_y: Self::generate_perm(sampler),
+            perm_z: Self::generate_perm(sampler),
+        }
+    }
+
+    /// Smooth noise in [-1, 1]
+    pub fn noise(&self, p: &Point3) -> f32 {
+        let (fx, fy, fz) = (p.x().floor(), p.y().floor(), p.z().floor());
+        let (u, v, w) = (p.x() - fx, p.y() - fy, p.z() - fz);
+        let (i, j, k) = (fx as i64, fy as i64, fz as i64);
+
+        // Hermite smoothing hides the lattice
+        let (uu, vv, ww) = (
+            u * u * (3.0 - 2.0 * u),
+            v * v * (3.0 - 2.0 * v),
+            w * w * (3.0 - 2.0 * w),
+        );
+
+        let mut accum = 0.0;
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let index = |perm: &[usize], x: i64| perm[(x & 255) as usize];
+                    let gradient = self.gradients[index(&self.perm_x, i + di)
+                        ^ index(&self.perm_y, j + dj)
+                        ^ index(&self.perm_z, k + dk)];
+
+                    let (fi, fj, fk) = (di as f32, dj as f32, dk as f32);
+                    let weight = Vec3::new(u - fi, v - fj, w - fk);
+                    accum += (fi * uu + (1.0 - fi) * (1.0 - uu))
+                        * (fj * vv + (1.0 - fj) * (1.0 - vv))
+                        * (fk * ww + (1.0 - fk) * (1.0 - ww))
+                        * gradient.dot(&weight);
+                }
+            }
+        }
+
+        accum
+    }
+
+    /// Sum of `depth` octaves of noise with halving amplitude
+    pub fn turbulence(&self, p: &Point3, depth: usize) -> f32 {
+        let mut accum = 0.0;
+        let mut temp_p = *p;
+        let mut weight = 1.0;
+
+        for _ in 0..depth {
+            accum += weight * self.noise(&temp_p);
+            weight *= 0.5;
+            temp_p = temp_p.scale(2.0);
+        }
+
+        accum.abs()
+    }
+
+    fn generate_perm(sampler: &mut Sampler) -> Vec<usize> {
+        let mut perm: Vec<usize> = (0..POINT_COUNT).collect();
+
+        // Fisher-Yates shuffle
+        for i in (1..POINT_COUNT).rev() {
+            let target = ((sampler.random_float() * (i + 1) as f32) as usize).min(i);
+            perm.swap(i, target);
+        }
+
+        perm
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoisePattern {
+    /// Plain smooth noise
+    Noise,
+    /// Octave sum of noise
+    Turbulence,
+    /// Sine stripes along z perturbed by turbulence
+    Marble,
+}
+
+pub struct NoiseTexture {
+    noise: Perlin,
+    scale: f32,
+    pattern: NoisePattern,
+    depth: usize,
+}
+
+impl NoiseTexture {
+    pub fn new(noise: Perlin, scale: f32, pattern: NoisePattern, depth: usize) -> Self {
+        Self {
+            noise,
+            scale,
+            pattern,
+            depth,
+        }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f32, _v: f32, p: &Point3) -> Colour {
+        let sp = p.scale(self.scale);
+        let grey = match self.pattern {
+            NoisePattern::Noise => 0.5 * (1.0 + self.noise.noise(&sp)),
+            NoisePattern::Turbulence => self.noise.turbulence(&sp, self.depth),
+            NoisePattern::Marble => {
+                0.5 * (1.0 + (sp.z() + 10.0 * self.noise.turbulence(p, self.depth)).sin())
+            }
+        };
+
+        Colour::new(grey, grey, grey)
+    }
+}
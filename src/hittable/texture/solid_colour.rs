@@ -0,0 +1,20 @@
+use crate::{Colour, Point3};
+
+use super::Texture;
+
+#[derive(Default, Clone, Copy)]
+pub struct SolidColour {
+    albedo: Colour,
+}
+
+impl SolidColour {
+    pub fn new(albedo: Colour) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Texture for SolidColour {
+    fn value(&self, _u: f32, _v: f32, _p: &Point3) -> Colour {
+        self.albedo
+    }
+}
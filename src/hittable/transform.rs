@@ -0,0 +1,323 @@
+use std::sync::Arc;
+
+use crate::{
+    utils::{interval::Interval, matrix::Mat4, sampler::Sampler},
+    Hittable, Point3, Ray, Vec3,
+};
+
+use super::{aabb::Aabb, HitRecord};
+
+/// Places `object` in the world through an affine matrix
+///
+/// Rays are carried into object space rather than moving the object, keeping their parameter
+/// `t` so hit distances stay comparable with everything else in the scene. Normals come back
+/// through the inverse transpose, which keeps them perpendicular under non-uniform scaling.
+/// The object is shared, so any number of transforms can place the same geometry.
+pub struct Transform {
+    object: Arc<dyn Hittable>,
+    to_world: Mat4,
+    to_object: Mat4,
+    /// Absolute determinant of the linear part of `to_world`
+    volume_scale: f32,
+    bbox: Aabb,
+}
+
+impl Transform {
+    /// `to_world` must be affine and invertible
+    pub fn new(object: Arc<dyn Hittable>, to_world: Mat4) -> Self {
+        debug_assert!(to_world.is_affine());
+        let to_object = to_world
+            .inverse()
+            .expect("object transforms must be invertible");
+
+        let bbox = transformed_box(&object.bounding_box(), &to_world);
+
+        Self {
+            object,
+            to_world,
+            to_object,
+            volume_scale: to_world.linear_determinant().abs(),
+            bbox,
+        }
+    }
+
+    fn to_object_ray(&self, r: &Ray) -> Ray {
+        carry(r, &self.to_object)
+    }
+}
+
+/// `r` carried through `to_object`, keeping its parameter `t` and its time
+fn carry(r: &Ray, to_object: &Mat4) -> Ray {
+    Ray::new(
+        to_object.transform_point(r.origin()),
+        to_object.transform_vector(r.direction()),
+        r.time(),
+    )
+}
+
+/// The box around all eight corners of `object_box` carried through `to_world`
+fn transformed_box(object_box: &Aabb, to_world: &Mat4) -> Aabb {
+    if object_box.is_empty() {
+        return Aabb::EMPTY;
+    }
+    corners(object_box)
+        .map(|p| to_world.transform_point(&p))
+        .fold(Aabb::EMPTY, |bbox, p| {
+            Aabb::enclosing(&bbox, &Aabb::from_points(p, p))
+        })
+}
+
+fn corners(bbox: &Aabb) -> impl Iterator<Item = Point3> + '_ {
+    (0..8).map(move |corner| {
+        let pick = |axis: usize| {
+            let range = bbox.axis_interval(axis);
+            if corner & (1 << axis) == 0 {
+                range.min
+            } else {
+                range.max
+            }
+        };
+        Point3::new(pick(0), pick(1), pick(2))
+    })
+}
+
+impl Hittable for Transform {
+    fn hit(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        if !self.object.hit(&self.to_object_ray(r), ray_t, rec) {
+            return false;
+        }
+
+        // Which side was hit carries over, the inverse transpose preserves the sign of
+        // dot(direction, normal)
+        rec.p = self.to_world.transform_point(&rec.p);
+        rec.tangent = self.to_world.transform_vector(&rec.tangent);
+        rec.normal = self
+            .to_object
+            .transpose_transform_vector(&rec.normal)
+            .unit_vector();
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f32 {
+        let local_direction = self.to_object.transform_vector(direction).unit_vector();
+        let pdf = self
+            .object
+            .pdf_value(&self.to_object.transform_point(origin), &local_direction);
+
+        // A linear map A stretches solid angle around a unit direction w by |det A| / |A w|^3
+        let stretched = self.to_world.transform_vector(&local_direction).length();
+        pdf * stretched * stretched * stretched / self.volume_scale
+    }
+
+    fn random(&self, origin: &Point3, sampler: &mut Sampler) -> Vec3 {
+        let local_origin = self.to_object.transform_point(origin);
+        self.to_world
+            .transform_vector(&self.object.random(&local_origin, sampler))
+    }
+
+    fn collide(
+        &self,
+        r: &Ray,
+        ray_t: Interval,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) -> bool {
+        if !self
+            .object
+            .collide(&self.to_object_ray(r), ray_t, rec, sampler)
+        {
+            return false;
+        }
+        rec.p = self.to_world.transform_point(&rec.p);
+        true
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: Interval, sampler: &mut Sampler) -> f32 {
+        self.object
+            .transmittance(&self.to_object_ray(r), ray_t, sampler)
+    }
+}
+
+/// Object to world matrix at a time in [0, 1]
+pub type Animation = Box<dyn Fn(f32) -> Mat4 + Send + Sync>;
+
+/// Places `object` through a matrix that changes over the shutter interval
+///
+/// Each ray is carried into object space by the inverse of the matrix at its own time, so
+/// the object is blurred along whatever path `animation` traces, rotations included. Rays
+/// before time 0 or after time 1 see the object at rest at the ends of its path. Nothing
+/// inside is sampled as a light, a moving emitter has no single position to aim at.
+pub struct MovingTransform {
+    object: Arc<dyn Hittable>,
+    animation: Animation,
+    bbox: Aabb,
+}
+
+impl MovingTransform {
+    /// Steps between the keyframes used to bound the motion
+    const BOUND_STEPS: usize = 64;
+
+    /// `animation` must give an affine, invertible matrix at every time in [0, 1]
+    pub fn new(object: Arc<dyn Hittable>, animation: Animation) -> Self {
+        // Boxes at evenly spaced times, each grown by half of the furthest any corner moves
+        // before the next one, which covers the bulge of a rotation between the two
+        let object_box = object.bounding_box();
+        let mut bbox = Aabb::EMPTY;
+        if !object_box.is_empty() {
+            let matrices: Vec<Mat4> = (0..=Self::BOUND_STEPS)
+                .map(|i| animation(i as f32 / Self::BOUND_STEPS as f32))
+                .collect();
+            for (i, to_world) in matrices.iter().enumerate() {
+                let next = &matrices[(i + 1).min(Self::BOUND_STEPS)];
+                let chord = corners(&object_box)
+                    .map(|p| (next.transform_point(&p) - to_world.transform_point(&p)).length())
+                    .fold(0.0, f32::max);
+                let sample = transformed_box(&object_box, to_world);
+                let padded = Aabb::new(
+                    sample.x.expand(chord),
+                    sample.y.expand(chord),
+                    sample.z.expand(chord),
+                );
+                bbox = Aabb::enclosing(&bbox, &padded);
+            }
+        }
+
+        Self {
+            object,
+            animation,
+            bbox,
+        }
+    }
+
+    /// The matrices to world and to object space at the time of `r`
+    fn matrices(&self, r: &Ray) -> (Mat4, Mat4) {
+        let to_world = (self.animation)(r.time().clamp(0.0, 1.0));
+        let to_object = to_world
+            .inverse()
+            .expect("animated transforms must stay invertible");
+        (to_world, to_object)
+    }
+}
+
+impl Hittable for MovingTransform {
+    fn hit(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        // Cheap rejection before building and inverting the matrix for this time
+        if !self.bbox.hit(r, ray_t) {
+            return false;
+        }
+        let (to_world, to_object) = self.matrices(r);
+        if !self.object.hit(&carry(r, &to_object), ray_t, rec) {
+            return false;
+        }
+
+        rec.p = to_world.transform_point(&rec.p);
+        rec.tangent = to_world.transform_vector(&rec.tangent);
+        rec.normal = to_object
+            .transpose_transform_vector(&rec.normal)
+            .unit_vector();
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn collide(
+        &self,
+        r: &Ray,
+        ray_t: Interval,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) -> bool {
+        let (to_world, to_object) = self.matrices(r);
+        if !self
+            .object
+            .collide(&carry(r, &to_object), ray_t, rec, sampler)
+        {
+            return false;
+        }
+        rec.p = to_world.transform_point(&rec.p);
+        true
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: Interval, sampler: &mut Sampler) -> f32 {
+        let (_, to_object) = self.matrices(r);
+        self.object
+            .transmittance(&carry(r, &to_object), ray_t, sampler)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        hittable::{material::lambertian::Lambertian, quad::Quad, sphere::Sphere},
+        Colour,
+    };
+
+    use super::*;
+
+    fn grey() -> Arc<Lambertian> {
+        Arc::new(Lambertian::new(Colour::new(0.5, 0.5, 0.5)))
+    }
+
+    #[test]
+    fn normals_stay_perpendicular_under_non_uniform_scale() {
+        // The unit sphere stretched into the ellipsoid x^2 / 4 + y^2 + z^2 = 1
+        let ellipsoid = Transform::new(
+            Arc::new(Sphere::new(Point3::default(), 1.0, grey())),
+            Mat4::scale(&Vec3::new(2.0, 1.0, 1.0)),
+        );
+        let origin = Point3::new(3.0, 3.0, 0.0);
+        let r = Ray::new(origin, Vec3::new(-1.0, -1.0, 0.0), 0.0);
+        let mut rec = HitRecord::default();
+        assert!(ellipsoid.hit(&r, Interval::new(0.001, f32::INFINITY), &mut rec));
+
+        let s = 2.0 / 5.0_f32.sqrt();
+        let expected_p = Point3::new(s, s, 0.0);
+        assert!((rec.p - expected_p).length() < 1e-4, "{:?}", rec.p);
+        assert!((r.at(rec.t) - expected_p).length() < 1e-4, "t = {}", rec.t);
+        let expected_normal = Vec3::new(1.0, 4.0, 0.0).unit_vector();
+        assert!(
+            (rec.normal - expected_normal).length() < 1e-4,
+            "{:?}",
+            rec.normal
+        );
+        assert!(rec.front_face);
+    }
+
+    #[test]
+    fn pdf_matches_the_transformed_shape() {
+        // A transformed quad is another quad, whose pdf is known directly
+        let to_world = Mat4::translate(&Vec3::new(0.5, 3.0, -1.0))
+            * Mat4::rotate(&Vec3::new(1.0, 0.3, 0.2), 25.0)
+            * Mat4::scale(&Vec3::new(2.0, 3.0, 1.0));
+        let (q, u, v) = (
+            Point3::new(-0.5, 0.0, -0.5),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        );
+        let placed = Transform::new(Arc::new(Quad::new(q, u, v, grey())), to_world);
+        let expected = Quad::new(
+            to_world.transform_point(&q),
+            to_world.transform_vector(&u),
+            to_world.transform_vector(&v),
+            grey(),
+        );
+
+        let origin = Point3::new(0.2, -1.0, 0.4);
+        for (a, b) in [(0.5, 0.5), (0.1, 0.9), (0.8, 0.3), (0.95, 0.05)] {
+            let target = to_world.transform_point(&(q + u.scale(a) + v.scale(b)));
+            let direction = target - origin;
+            let want = expected.pdf_value(&origin, &direction);
+            let got = placed.pdf_value(&origin, &direction);
+            assert!(want > 0.0);
+            assert!((got - want).abs() < 1e-3 * want, "{got} != {want}");
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use crate::utils::interval::Interval;
+use crate::{Hittable, Point3, Ray, Vec3};
+
+use super::aabb::Aabb;
+use super::material::Material;
+use super::HitRecord;
+
+/// Triangle with vertices `a`, `b` and `c`, the front face is wound counter-clockwise
+pub struct Triangle {
+    a: Point3,
+    e1: Vec3,
+    e2: Vec3,
+    normal: Vec3,
+    mat: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+impl Triangle {
+    pub fn new(a: Point3, b: Point3, c: Point3, mat: Arc<dyn Material>) -> Self {
+        let e1 = b - a;
+        let e2 = c - a;
+
+        Triangle {
+            a,
+            e1,
+            e2,
+            normal: e1.cross(&e2).unit_vector(),
+            mat,
+            bbox: Aabb::enclosing(&Aabb::from_points(a, b), &Aabb::from_points(a, c)),
+        }
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, r: &Ray, ray_t: Interval, rec: &mut HitRecord) -> bool {
+        // Möller-Trumbore, solves for t and the barycentric weights of b and c together
+        let pvec = r.direction().cross(&self.e2);
+        let det = self.e1.dot(&pvec);
+
+        // Parallel to the plane
+        if det.abs() < 1e-8 {
+            return false;
+        }
+
+        let inv_det = 1.0 / det;
+        let tvec = *r.origin() - self.a;
+        let u = tvec.dot(&pvec) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return false;
+        }
+
+        let qvec = tvec.cross(&self.e1);
+        let v = r.direction().dot(&qvec) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return false;
+        }
+
+        let t = self.e2.dot(&qvec) * inv_det;
+        if !ray_t.surrounds(t) {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = r.at(t);
+        rec.u = u;
+        rec.v = v;
+        rec.tangent = self.e1;
+        rec.set_face_normal(r, &self.normal);
+        rec.mat = Arc::clone(&self.mat);
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
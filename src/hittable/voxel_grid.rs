@@ -0,0 +1,136 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::{utils::interval::Interval, Point3};
+
+use super::aabb::Aabb;
+
+/// Dense grid of scalar samples at voxel centres spread over a box, such as the density or
+/// temperature of a simulated volume
+pub struct VoxelGrid {
+    resolution: [usize; 3],
+    bbox: Aabb,
+    values: Vec<f32>,
+}
+
+impl VoxelGrid {
+    /// `values` run along x first, then y, then z
+    pub fn new(resolution: [usize; 3], bbox: Aabb, values: Vec<f32>) -> Self {
+        assert_eq!(values.len(), resolution.iter().product::<usize>());
+        Self {
+            resolution,
+            bbox,
+            values,
+        }
+    }
+
+    /// Reads a Mitsuba `.vol` file: the bytes `VOL` and version 3, then little-endian 32-bit
+    /// encoding, x, y and z resolution, channel count and the bounding box as six floats, followed
+    /// by the samples with x varying fastest. Float32 and uint8 encodings with one channel are
+    /// supported, uint8 samples map to [0, 1]
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        let invalid =
+            |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+        if bytes.len() < 48 || &bytes[..3] != b"VOL" || bytes[3] != 3 {
+            return Err(invalid("not a version 3 Mitsuba volume file"));
+        }
+        let word = |i: usize| {
+            [
+                bytes[4 + 4 * i],
+                bytes[5 + 4 * i],
+                bytes[6 + 4 * i],
+                bytes[7 + 4 * i],
+            ]
+        };
+        let int = |i: usize| i32::from_le_bytes(word(i));
+        let float = |i: usize| f32::from_le_bytes(word(i));
+
+        let encoding = int(0);
+        let resolution = [int(1), int(2), int(3)];
+        if resolution.iter().any(|&n| n <= 0) {
+            return Err(invalid("volume resolution must be positive"));
+        }
+        let resolution = resolution.map(|n| n as usize);
+        if int(4) != 1 {
+            return Err(invalid("only single channel volumes are supported"));
+        }
+        let bbox = Aabb::new(
+            Interval::new(float(5), float(8)),
+            Interval::new(float(6), float(9)),
+            Interval::new(float(7), float(10)),
+        );
+        if bbox.is_empty() {
+            return Err(invalid("volume bounding box is inverted"));
+        }
+
+        let count = resolution
+            .iter()
+            .try_fold(1usize, |count, &n| count.checked_mul(n))
+            .ok_or_else(|| invalid("volume resolution is too large"))?;
+        let data = &bytes[48..];
+        let values: Vec<f32> = match encoding {
+            1 if data.len() / 4 >= count => data
+                .chunks_exact(4)
+                .take(count)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect(),
+            3 if data.len() >= count => data[..count].iter().map(|&b| b as f32 / 255.0).collect(),
+            1 | 3 => return Err(invalid("volume data is truncated")),
+            _ => {
+                return Err(invalid(
+                    "only float32 and uint8 volume encodings are supported",
+                ))
+            }
+        };
+        if values.iter().any(|v| !v.is_finite()) {
+            return Err(invalid("volume holds a non-finite value"));
+        }
+
+        Ok(Self::new(resolution, bbox, values))
+    }
+
+    pub fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    pub fn max_value(&self) -> f32 {
+        self.values.iter().copied().fold(0.0, f32::max)
+    }
+
+    /// Trilinear interpolation of the samples around `p`, edge samples extend to the faces of
+    /// the box and everything outside is zero
+    pub fn lookup(&self, p: &Point3) -> f32 {
+        let mut base = [0usize; 3];
+        let mut frac = [0.0f32; 3];
+        for axis in 0..3 {
+            let range = self.bbox.axis_interval(axis);
+            if !(range.min..=range.max).contains(&p[axis]) {
+                return 0.0;
+            }
+            let n = self.resolution[axis];
+            let x =
+                ((p[axis] - range.min) / range.size() * n as f32 - 0.5).clamp(0.0, (n - 1) as f32);
+            base[axis] = (x as usize).min(n.saturating_sub(2));
+            frac[axis] = x - base[axis] as f32;
+        }
+
+        let [nx, ny, nz] = self.resolution;
+        let sample = |dx: usize, dy: usize, dz: usize| {
+            let x = (base[0] + dx).min(nx - 1);
+            let y = (base[1] + dy).min(ny - 1);
+            let z = (base[2] + dz).min(nz - 1);
+            self.values[(z * ny + y) * nx + x]
+        };
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+
+        let [fx, fy, fz] = frac;
+        let x00 = lerp(sample(0, 0, 0), sample(1, 0, 0), fx);
+        let x10 = lerp(sample(0, 1, 0), sample(1, 1, 0), fx);
+        let x01 = lerp(sample(0, 0, 1), sample(1, 0, 1), fx);
+        let x11 = lerp(sample(0, 1, 1), sample(1, 1, 1), fx);
+        lerp(lerp(x00, x10, fy), lerp(x01, x11, fy), fz)
+    }
+}
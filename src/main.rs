@@ -1,105 +1,68 @@
+mod cli;
 mod hittable;
+mod output;
 mod scene;
 mod utils;
 
-use std::rc::Rc;
+use std::process;
 
-use hittable::{
-    material::{dielectric::Dielectric, lambertian::Lambertian, metal::Metal},
-    {sphere::Sphere, HitRecord, Hittable, HittableList},
-};
-use scene::{camera::Camera, ray::Ray};
+use clap::Parser;
+
+use cli::Cli;
+use hittable::{HitRecord, Hittable};
+use output::ImageFormat;
+use scene::{camera::Camera, ray::Ray, Scene};
 
 use utils::{
-    colour::{write_colour, Colour},
-    random_float, random_float_range,
+    colour::Colour,
     vec3::{Point3, Vec3},
 };
 
 fn main() {
-    let mut world = HittableList::new();
-
-    let ground_material = Lambertian::new(Colour::new(0.5, 0.5, 0.5));
-    let ground_sphere = Sphere::new(
-        Point3::new(0.0, -1000.0, 0.0),
-        1000.0,
-        Rc::new(ground_material),
-    );
-    world.add(Box::new(ground_sphere));
+    let cli = Cli::parse();
 
-    for a in -11..11 {
-        for b in -11..11 {
-            let choose_mat = random_float();
-            let center = Point3::new(
-                a as f32 + 0.9 * random_float(),
-                0.2,
-                b as f32 + 0.9 * random_float(),
-            );
-
-            if (center - Point3::new(4.0, 0.2, 0.0)).length() > 0.9 {
-                if choose_mat < 0.8 {
-                    // Diffuse
-                    let albedo: Colour = Vec3::random() * Vec3::random();
-                    world.add(Box::new(Sphere::new(
-                        center,
-                        0.2,
-                        Rc::new(Lambertian::new(albedo)),
-                    )));
-                } else if choose_mat < 0.95 {
-                    // Metal
-                    let albedo = Vec3::random_range(0.5, 1.0);
-                    let fuzz = random_float_range(0.0, 0.5);
-
-                    world.add(Box::new(Sphere::new(
-                        center,
-                        0.2,
-                        Rc::new(Metal::new(albedo, fuzz)),
-                    )));
-                } else {
-                    // Glass
-                    world.add(Box::new(Sphere::new(
-                        center,
-                        0.2,
-                        Rc::new(Dielectric::new(1.5)),
-                    )));
-                };
-            }
-        }
+    if let Err(message) = run(&cli) {
+        eprintln!("error: {}", message);
+        process::exit(1);
     }
+}
 
-    let material1 = Rc::new(Dielectric::new(1.5));
-    world.add(Box::new(Sphere::new(
-        Point3::new(0.0, 1.0, 0.0),
-        1.0,
-        material1,
-    )));
-
-    let material2 = Rc::new(Lambertian::new(Colour::new(0.4, 0.2, 0.1)));
-    world.add(Box::new(Sphere::new(
-        Point3::new(-4.0, 1.0, 0.0),
-        1.0,
-        material2,
-    )));
-
-    let material3 = Rc::new(Metal::new(Colour::new(0.7, 0.6, 0.5), 0.0));
-    world.add(Box::new(Sphere::new(
-        Point3::new(4.0, 1.0, 0.0),
-        1.0,
-        material3,
-    )));
+fn run(cli: &Cli) -> Result<(), String> {
+    // Settle the output format before spending any time rendering
+    let format = cli
+        .format
+        .or_else(|| ImageFormat::from_path(&cli.output))
+        .ok_or_else(|| {
+            format!(
+                "cannot infer an image format from {}, pass --format",
+                cli.output.display()
+            )
+        })?;
 
-    let mut cam = Camera::default();
+    let overrides = cli.camera_overrides();
+    let scene = match &cli.scene {
+        Some(path) => {
+            Scene::load(path, &overrides).map_err(|e| format!("{}: {}", path.display(), e))?
+        }
+        None => Scene::preset(&cli.preset, &overrides).map_err(|e| e.to_string())?,
+    };
+    let Scene {
+        mut camera,
+        world,
+        lights,
+        media,
+    } = scene;
 
-    cam.aspect_ratio = 16.0 / 9.0;
-    cam.image_width = 400;
-    cam.samples_per_pixel = 100;
-    cam.max_depth = 50;
-    cam.vfov = 20.0;
-    cam.lookfrom = Point3::new(13.0, 2.0, 3.0);
-    cam.lookat = Point3::new(0.0, 0.0, 0.0);
-    cam.vup = Point3::new(0.0, 1.0, 0.0);
-    cam.defocus_angle = 0.6;
-    cam.focus_dist = 10.0;
+    let world = world.into_bvh();
+    if cli.stats {
+        let stats = world.stats();
+        eprintln!(
+            "BVH: {} nodes, {} leaves, {} primitives, depth {}",
+            stats.nodes, stats.leaves, stats.primitives, stats.max_depth
+        );
+    }
 
-    cam.render(&mut world)
+    let image = camera.render(&world, &lights, &media);
+    output::write_image(&cli.output, format, &image)
+        .map_err(|e| format!("cannot write {}: {}", cli.output.display(), e))
 }
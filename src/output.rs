@@ -0,0 +1,88 @@
+pub mod exr;
+pub mod hdr;
+pub mod png;
+pub mod ppm;
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::utils::framebuffer::Framebuffer;
+
+use self::{
+    exr::{ExrCompression, ExrWriter},
+    hdr::HdrWriter,
+    png::PngWriter,
+    ppm::PpmWriter,
+};
+
+pub trait ImageWriter {
+    fn write(&self, image: &Framebuffer, out: &mut dyn Write) -> io::Result<()>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    /// Binary P6 PPM
+    Ppm,
+    /// Plain text P3 PPM
+    PpmAscii,
+    /// Linear float OpenEXR with ZIP compression
+    Exr,
+    /// Linear float OpenEXR without compression
+    ExrUncompressed,
+    /// Linear Radiance RGBE
+    Hdr,
+}
+
+impl ImageFormat {
+    /// Picks a format from the file extension, `.ppm` files are written as binary P6
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "png" => Some(Self::Png),
+            "ppm" => Some(Self::Ppm),
+            "exr" => Some(Self::Exr),
+            "hdr" => Some(Self::Hdr),
+            _ => None,
+        }
+    }
+
+    pub fn writer(&self) -> Box<dyn ImageWriter> {
+        match self {
+            Self::Png => Box::new(PngWriter),
+            Self::Ppm => Box::new(PpmWriter { binary: true }),
+            Self::PpmAscii => Box::new(PpmWriter { binary: false }),
+            Self::Exr => Box::new(ExrWriter {
+                compression: ExrCompression::Zip,
+            }),
+            Self::ExrUncompressed => Box::new(ExrWriter {
+                compression: ExrCompression::None,
+            }),
+            Self::Hdr => Box::new(HdrWriter),
+        }
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "png" => Ok(Self::Png),
+            "ppm" | "p6" => Ok(Self::Ppm),
+            "ppm-ascii" | "p3" => Ok(Self::PpmAscii),
+            "exr" | "exr-zip" => Ok(Self::Exr),
+            "exr-uncompressed" => Ok(Self::ExrUncompressed),
+            "hdr" => Ok(Self::Hdr),
+            _ => Err(format!("unknown image format '{}'", s)),
+        }
+    }
+}
+
+pub fn write_image(path: &Path, format: ImageFormat, image: &Framebuffer) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    format.writer().write(image, &mut out)?;
+    out.flush()
+}
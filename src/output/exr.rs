@@ -0,0 +1,175 @@
+use std::io::{self, Write};
+
+use flate2::{write::ZlibEncoder, Compression};
+
+use crate::utils::framebuffer::Framebuffer;
+
+use super::ImageWriter;
+
+const MAGIC: u32 = 20000630;
+const VERSION: u32 = 2;
+// Pixel type identifier for 32-bit floats in a channel list
+const PIXEL_TYPE_FLOAT: i32 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExrCompression {
+    None,
+    /// Zlib over blocks of 16 scanlines
+    Zip,
+}
+
+impl ExrCompression {
+    fn id(&self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Zip => 3,
+        }
+    }
+
+    fn lines_per_chunk(&self) -> usize {
+        match self {
+            Self::None => 1,
+            Self::Zip => 16,
+        }
+    }
+}
+
+/// Scanline OpenEXR writer with 32-bit float RGB channels, stores linear radiance as is
+pub struct ExrWriter {
+    pub compression: ExrCompression,
+}
+
+impl ImageWriter for ExrWriter {
+    fn write(&self, image: &Framebuffer, out: &mut dyn Write) -> io::Result<()> {
+        let (width, height) = (image.width as i32, image.height as i32);
+        let mut header = Vec::new();
+
+        header.extend_from_slice(&MAGIC.to_le_bytes());
+        header.extend_from_slice(&VERSION.to_le_bytes());
+
+        // Channels are stored in alphabetical order
+        let mut channels = Vec::new();
+        for name in ["B", "G", "R"] {
+            channels.extend_from_slice(name.as_bytes());
+            channels.push(0);
+            channels.extend_from_slice(&PIXEL_TYPE_FLOAT.to_le_bytes());
+            // pLinear and three reserved bytes, then x and y sampling
+            channels.extend_from_slice(&[0, 0, 0, 0]);
+            channels.extend_from_slice(&1i32.to_le_bytes());
+            channels.extend_from_slice(&1i32.to_le_bytes());
+        }
+        channels.push(0);
+        write_attribute(&mut header, "channels", "chlist", &channels);
+
+        write_attribute(
+            &mut header,
+            "compression",
+            "compression",
+            &[self.compression.id()],
+        );
+
+        let window = box2i(0, 0, width - 1, height - 1);
+        write_attribute(&mut header, "dataWindow", "box2i", &window);
+        write_attribute(&mut header, "displayWindow", "box2i", &window);
+
+        // Increasing y
+        write_attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+        write_attribute(
+            &mut header,
+            "pixelAspectRatio",
+            "float",
+            &1f32.to_le_bytes(),
+        );
+
+        let mut centre = 0f32.to_le_bytes().to_vec();
+        centre.extend_from_slice(&0f32.to_le_bytes());
+        write_attribute(&mut header, "screenWindowCenter", "v2f", &centre);
+        write_attribute(
+            &mut header,
+            "screenWindowWidth",
+            "float",
+            &1f32.to_le_bytes(),
+        );
+        header.push(0);
+
+        let chunks: Vec<Vec<u8>> = image
+            .pixels
+            .chunks(image.width * self.compression.lines_per_chunk())
+            .map(|block| self.encode_block(block, image.width))
+            .collect::<io::Result<_>>()?;
+
+        // The offset table follows the header and holds the file position of every chunk
+        let mut offset = (header.len() + 8 * chunks.len()) as u64;
+        out.write_all(&header)?;
+        for chunk in &chunks {
+            out.write_all(&offset.to_le_bytes())?;
+            offset += 8 + chunk.len() as u64;
+        }
+
+        let lines_per_chunk = self.compression.lines_per_chunk();
+        for (n, chunk) in chunks.iter().enumerate() {
+            out.write_all(&((n * lines_per_chunk) as i32).to_le_bytes())?;
+            out.write_all(&(chunk.len() as i32).to_le_bytes())?;
+            out.write_all(chunk)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ExrWriter {
+    fn encode_block(&self, block: &[crate::Colour], width: usize) -> io::Result<Vec<u8>> {
+        // Each scanline holds all of its B values, then G, then R
+        let mut raw = Vec::with_capacity(block.len() * 12);
+        for row in block.chunks_exact(width) {
+            for channel in [2, 1, 0] {
+                for pixel in row {
+                    raw.extend_from_slice(&pixel[channel].to_le_bytes());
+                }
+            }
+        }
+
+        if self.compression == ExrCompression::None {
+            return Ok(raw);
+        }
+
+        // Split even and odd bytes into two halves, then delta encode the result
+        let half = raw.len().div_ceil(2);
+        let mut predicted = vec![0u8; raw.len()];
+        for (i, &byte) in raw.iter().enumerate() {
+            predicted[if i % 2 == 0 { i / 2 } else { half + i / 2 }] = byte;
+        }
+        for i in (1..predicted.len()).rev() {
+            predicted[i] = predicted[i]
+                .wrapping_sub(predicted[i - 1])
+                .wrapping_add(128);
+        }
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&predicted)?;
+        let compressed = encoder.finish()?;
+
+        // Readers treat a chunk that did not shrink as uncompressed
+        Ok(if compressed.len() < raw.len() {
+            compressed
+        } else {
+            raw
+        })
+    }
+}
+
+fn write_attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(kind.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+fn box2i(x_min: i32, y_min: i32, x_max: i32, y_max: i32) -> Vec<u8> {
+    [x_min, y_min, x_max, y_max]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect()
+}
@@ -0,0 +1,98 @@
+use std::io::{self, Write};
+
+use crate::utils::{colour::Colour, framebuffer::Framebuffer};
+
+use super::ImageWriter;
+
+// Scanlines outside this range cannot be run-length encoded
+const MIN_RLE_WIDTH: usize = 8;
+const MAX_RLE_WIDTH: usize = 0x7fff;
+
+/// Radiance RGBE writer, stores linear radiance without clamping or gamma
+pub struct HdrWriter;
+
+impl ImageWriter for HdrWriter {
+    fn write(&self, image: &Framebuffer, out: &mut dyn Write) -> io::Result<()> {
+        write!(
+            out,
+            "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+            image.height, image.width
+        )?;
+
+        for row in image.pixels.chunks_exact(image.width) {
+            let rgbe: Vec<[u8; 4]> = row.iter().map(|&c| to_rgbe(c)).collect();
+
+            if !(MIN_RLE_WIDTH..=MAX_RLE_WIDTH).contains(&image.width) {
+                for pixel in &rgbe {
+                    out.write_all(pixel)?;
+                }
+                continue;
+            }
+
+            let width = image.width as u16;
+            out.write_all(&[2, 2, (width >> 8) as u8, width as u8])?;
+            for channel in 0..4 {
+                let bytes: Vec<u8> = rgbe.iter().map(|pixel| pixel[channel]).collect();
+                write_rle_channel(&bytes, out)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn to_rgbe(c: Colour) -> [u8; 4] {
+    let (r, g, b) = (c.x().max(0.0), c.y().max(0.0), c.z().max(0.0));
+    let v = r.max(g).max(b);
+    if !v.is_finite() || v < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+
+    // v = mantissa * 2^exponent with mantissa in [0.5, 1)
+    let mut exponent = v.log2().floor() as i32 + 1;
+    if v / 2f32.powi(exponent) >= 1.0 {
+        exponent += 1;
+    }
+    let scale = 256.0 / 2f32.powi(exponent);
+
+    [
+        (r * scale) as u8,
+        (g * scale) as u8,
+        (b * scale) as u8,
+        (exponent + 128).clamp(0, 255) as u8,
+    ]
+}
+
+// Runs are encoded as (128 + length, value) and literal dumps as (length, bytes...)
+fn write_rle_channel(bytes: &[u8], out: &mut dyn Write) -> io::Result<()> {
+    const MIN_RUN: usize = 4;
+    const MAX_LENGTH: usize = 127;
+
+    let run_length = |start: usize| {
+        bytes[start..]
+            .iter()
+            .take(MAX_LENGTH)
+            .take_while(|&&b| b == bytes[start])
+            .count()
+    };
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let run = run_length(i);
+        if run >= MIN_RUN {
+            out.write_all(&[128 + run as u8, bytes[i]])?;
+            i += run;
+            continue;
+        }
+
+        // Dump literals up to the start of the next worthwhile run
+        let start = i;
+        while i < bytes.len() && i - start < MAX_LENGTH && run_length(i) < MIN_RUN {
+            i += 1;
+        }
+        out.write_all(&[(i - start) as u8])?;
+        out.write_all(&bytes[start..i])?;
+    }
+
+    Ok(())
+}
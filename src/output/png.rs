@@ -0,0 +1,22 @@
+use std::io::{self, Write};
+
+use crate::utils::framebuffer::Framebuffer;
+
+use super::ImageWriter;
+
+pub struct PngWriter;
+
+impl ImageWriter for PngWriter {
+    fn write(&self, image: &Framebuffer, out: &mut dyn Write) -> io::Result<()> {
+        let mut encoder = ::png::Encoder::new(out, image.width as u32, image.height as u32);
+        encoder.set_color(::png::ColorType::Rgb);
+        encoder.set_depth(::png::BitDepth::Eight);
+        // Samples are gamma 2 encoded, see `colour::to_rgb8`
+        encoder.set_source_gamma(::png::ScaledFloat::new(0.5));
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&image.to_rgb8())?;
+        writer.finish()?;
+        Ok(())
+    }
+}
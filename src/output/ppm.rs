@@ -0,0 +1,27 @@
+use std::io::{self, Write};
+
+use crate::utils::framebuffer::Framebuffer;
+
+use super::ImageWriter;
+
+pub struct PpmWriter {
+    /// Writes P6 binary samples instead of P3 text
+    pub binary: bool,
+}
+
+impl ImageWriter for PpmWriter {
+    fn write(&self, image: &Framebuffer, out: &mut dyn Write) -> io::Result<()> {
+        let magic = if self.binary { "P6" } else { "P3" };
+        write!(out, "{}\n{} {}\n255\n", magic, image.width, image.height)?;
+
+        let rgb = image.to_rgb8();
+        if self.binary {
+            return out.write_all(&rgb);
+        }
+
+        for pixel in rgb.chunks_exact(3) {
+            writeln!(out, "{} {} {}", pixel[0], pixel[1], pixel[2])?;
+        }
+        Ok(())
+    }
+}
@@ -1,2 +1,76 @@
+pub mod background;
 pub mod camera;
+pub mod description;
+pub mod obj;
+pub mod presets;
 pub mod ray;
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::hittable::HittableList;
+
+use self::{
+    camera::Camera,
+    description::{CameraOverrides, SceneDescription},
+};
+
+pub struct Scene {
+    pub camera: Camera,
+    pub world: HittableList,
+    /// Emissive spheres, quads and boxes, sampled directly at every diffuse bounce
+    pub lights: HittableList,
+    /// Fog, smoke and other participating media, kept out of `world` so that shadow rays can
+    /// pass through them
+    pub media: HittableList,
+}
+
+#[derive(Debug)]
+pub struct SceneError {
+    /// 1-based line in the scene file the error points at, when known
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl Error for SceneError {}
+
+impl Scene {
+    /// Loads a TOML scene description from `path`
+    pub fn load(path: &Path, overrides: &CameraOverrides) -> Result<Self, SceneError> {
+        let source = fs::read_to_string(path).map_err(|e| SceneError {
+            line: None,
+            message: format!("cannot read {}: {}", path.display(), e),
+        })?;
+
+        let mut description = SceneDescription::from_toml(&source)?;
+        description.camera.get_mut().apply(overrides);
+        let base_dir = path.parent().unwrap_or(Path::new("."));
+        description.build(&source, base_dir)
+    }
+
+    /// Builds one of the scenes from `presets::NAMES`, the override seed also lays out the scene
+    pub fn preset(name: &str, overrides: &CameraOverrides) -> Result<Self, SceneError> {
+        let mut description =
+            presets::description(name, overrides.seed.unwrap_or(0)).ok_or_else(|| SceneError {
+                line: None,
+                message: format!(
+                    "unknown preset '{}', expected one of: {}",
+                    name,
+                    presets::NAMES.join(", ")
+                ),
+            })?;
+        description.camera.get_mut().apply(overrides);
+        description.build("", Path::new("."))
+    }
+}
@@ -0,0 +1,60 @@
+pub mod environment_map;
+pub mod sky;
+
+use crate::{utils::sampler::Sampler, Colour, Vec3};
+
+/// Radiance for rays that leave the scene without hitting anything
+pub trait Environment: Send + Sync {
+    fn radiance(&self, direction: &Vec3) -> Colour;
+
+    /// Whether `sample` should be used to light the scene directly
+    fn is_sampled(&self) -> bool {
+        false
+    }
+
+    /// Solid angle density of `sample` producing `direction`
+    fn pdf_value(&self, _direction: &Vec3) -> f32 {
+        0.0
+    }
+
+    fn sample(&self, _sampler: &mut Sampler) -> Vec3 {
+        Vec3::new(0.0, 1.0, 0.0)
+    }
+}
+
+/// Simple procedural backgrounds
+#[derive(Clone, Copy, Debug)]
+pub enum Background {
+    /// Black, every bit of light has to come from emissive materials
+    None,
+    Solid(Colour),
+    /// Vertical blend from `bottom` when looking straight down to `top` when looking straight up
+    Gradient {
+        bottom: Colour,
+        top: Colour,
+    },
+}
+
+impl Environment for Background {
+    fn radiance(&self, direction: &Vec3) -> Colour {
+        match *self {
+            Self::None => Colour::default(),
+            Self::Solid(colour) => colour,
+            Self::Gradient { bottom, top } => {
+                let unit_direction = direction.unit_vector();
+                let a = 0.5 * (unit_direction.y() + 1.0);
+                bottom.scale(1.0 - a) + top.scale(a)
+            }
+        }
+    }
+}
+
+impl Default for Background {
+    /// The white to blue sky from the book
+    fn default() -> Self {
+        Self::Gradient {
+            bottom: Colour::new(1.0, 1.0, 1.0),
+            top: Colour::new(0.5, 0.7, 1.0),
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::f32::consts::PI;
+
+use crate::{hittable::texture::image::ImageData, utils::sampler::Sampler, Colour, Vec3};
+
+use super::Environment;
+
+/// Equirectangular image surrounding the scene, importance sampled by luminance
+///
+/// The centre of the image faces +x and its top edge is straight up. `rotation` turns the map
+/// around the vertical axis, counter-clockwise seen from above.
+pub struct EnvironmentMap {
+    image: ImageData,
+    rotation: f32,
+    intensity: f32,
+    /// Cumulative weight of each row, top to bottom, ending at 1
+    marginal_cdf: Vec<f32>,
+    /// Cumulative weight of each texel within its row, each row ending at 1
+    conditional_cdf: Vec<f32>,
+    /// Weight of each texel divided by the mean, the density per unit of image area
+    density: Vec<f32>,
+}
+
+fn luminance(c: Colour) -> f32 {
+    0.2126 * c.x() + 0.7152 * c.y() + 0.0722 * c.z()
+}
+
+// Index of the first entry of a non-decreasing `cdf` above `x`
+fn search(cdf: &[f32], x: f32) -> usize {
+    cdf.partition_point(|&c| c <= x).min(cdf.len() - 1)
+}
+
+impl EnvironmentMap {
+    /// `rotation` is in degrees, `intensity` scales every texel
+    pub fn new(image: ImageData, rotation: f32, intensity: f32) -> Self {
+        let (width, height) = (image.width, image.height);
+
+        // Rows near the poles cover less solid angle and are weighted down to match
+        let weights: Vec<f32> = (0..width * height)
+            .map(|i| {
+                let theta = ((i / width) as f32 + 0.5) / height as f32 * PI;
+                luminance(image.texels[i]).max(0.0) * theta.sin()
+            })
+            .collect();
+
+        let mut conditional_cdf = Vec::with_capacity(weights.len());
+        let mut row_sums = Vec::with_capacity(height);
+        for row in weights.chunks_exact(width.max(1)) {
+            let sum: f32 = row.iter().sum();
+            let mut running = 0.0;
+            for (i, &w) in row.iter().enumerate() {
+                running += w;
+                conditional_cdf.push(if sum > 0.0 {
+                    running / sum
+                } else {
+                    (i + 1) as f32 / width as f32
+                });
+            }
+            row_sums.push(sum);
+        }
+
+        let total: f32 = row_sums.iter().sum();
+        let mut running = 0.0;
+        let marginal_cdf = row_sums
+            .iter()
+            .map(|&sum| {
+                running += sum;
+                running / total
+            })
+            .collect();
+
+        let mean = total / weights.len().max(1) as f32;
+        let density = weights
+            .iter()
+            .map(|&w| if total > 0.0 { w / mean } else { 0.0 })
+            .collect();
+
+        Self {
+            image,
+            rotation: rotation.to_radians(),
+            intensity,
+            marginal_cdf,
+            conditional_cdf,
+            density,
+        }
+    }
+
+    /// Texel hit by a unit `direction` and the polar angle of the direction
+    fn texel_index(&self, direction: &Vec3) -> (usize, f32) {
+        let (sin_r, cos_r) = self.rotation.sin_cos();
+        let x = direction.x() * cos_r - direction.z() * sin_r;
+        let z = direction.x() * sin_r + direction.z() * cos_r;
+
+        // Same longitude convention as `Sphere`, with theta measured from straight up
+        let theta = direction.y().clamp(-1.0, 1.0).acos();
+        let phi = (-z).atan2(x) + PI;
+        let u = phi / (2.0 * PI);
+        let v = theta / PI;
+
+        let i = ((u * self.image.width as f32) as usize).min(self.image.width - 1);
+        let j = ((v * self.image.height as f32) as usize).min(self.image.height - 1);
+        (j * self.image.width + i, theta)
+    }
+}
+
+impl Environment for EnvironmentMap {
+    fn radiance(&self, direction: &Vec3) -> Colour {
+        if self.image.texels.is_empty() {
+            return Colour::default();
+        }
+
+        let (index, _) = self.texel_index(&direction.unit_vector());
+        self.image.texels[index].scale(self.intensity)
+    }
+
+    fn is_sampled(&self) -> bool {
+        self.marginal_cdf
+            .last()
+            .is_some_and(|total| total.is_finite())
+    }
+
+    fn pdf_value(&self, direction: &Vec3) -> f32 {
+        if !self.is_sampled() {
+            return 0.0;
+        }
+
+        let (index, theta) = self.texel_index(&direction.unit_vector());
+        let sin_theta = theta.sin();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+
+        // The image spans 2 pi by pi radians of longitude and polar angle
+        self.density[index] / (2.0 * PI * PI * sin_theta)
+    }
+
+    fn sample(&self, sampler: &mut Sampler) -> Vec3 {
+        let width = self.image.width;
+        let j = search(&self.marginal_cdf, sampler.random_float());
+        let row = &self.conditional_cdf[j * width..(j + 1) * width];
+        let i = search(row, sampler.random_float());
+
+        let u = (i as f32 + sampler.random_float()) / width as f32;
+        let v = (j as f32 + sampler.random_float()) / self.image.height as f32;
+        let theta = v * PI;
+        let phi = u * 2.0 * PI - PI;
+
+        // Inverse of `texel_index`
+        let (x, z) = (phi.cos() * theta.sin(), -phi.sin() * theta.sin());
+        let (sin_r, cos_r) = self.rotation.sin_cos();
+        Vec3::new(x * cos_r + z * sin_r, theta.cos(), -x * sin_r + z * cos_r)
+    }
+}
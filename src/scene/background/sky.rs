@@ -0,0 +1,260 @@
+use std::f32::consts::PI;
+
+use crate::{
+    hittable::pdf::{CosinePdf, Pdf},
+    utils::{onb::Onb, sampler::Sampler},
+    Colour, Vec3,
+};
+
+use super::Environment;
+
+// Sky luminance comes out of the model in kcd/m², this scale keeps a sunlit white surface
+// around 1 without any exposure control
+const LUMINANCE_SCALE: f32 = 0.04;
+// Sun illuminance at the top of the atmosphere in the same units, about 120 klux
+const SOLAR_ILLUMINANCE: f32 = 120.0 * LUMINANCE_SCALE;
+// Wavelengths in micrometres used for the red, green and blue sun transmittance
+const WAVELENGTHS: [f32; 3] = [0.68, 0.55, 0.44];
+
+/// Perez et al. sky luminance distribution, five coefficients per quantity
+#[derive(Clone, Copy)]
+struct Perez([f32; 5]);
+
+impl Perez {
+    fn new(turbidity: f32, coefficients: [[f32; 2]; 5]) -> Self {
+        Self(coefficients.map(|[slope, offset]| slope * turbidity + offset))
+    }
+
+    /// Relative luminance at zenith angle `theta` and angle `gamma` from the sun
+    fn f(&self, theta: f32, gamma: f32) -> f32 {
+        let [a, b, c, d, e] = self.0;
+        let cos_theta = theta.cos().max(1e-3);
+        (1.0 + a * (b / cos_theta).exp()) * (1.0 + c * (d * gamma).exp() + e * gamma.cos().powi(2))
+    }
+}
+
+/// Preetham's analytic daylight model with a sampled sun disc
+///
+/// Directions below the horizon see the sky colour at the horizon, so a ground plane that ends
+/// before the horizon does not leave a black band.
+pub struct PhysicalSky {
+    sun_direction: Vec3,
+    sun_cos_radius: f32,
+    sun_radiance: Colour,
+    /// Perez distributions of luminance Y and chromaticities x and y
+    perez: [Perez; 3],
+    /// Zenith values of Y, x and y divided by the Perez function at the zenith
+    zenith: [f32; 3],
+    intensity: f32,
+    sun_probability: f32,
+}
+
+impl PhysicalSky {
+    /// `elevation` and `azimuth` are in degrees, azimuth clockwise from north (-z) towards east
+    /// (+x). `sun_diameter` is the angular diameter of the sun in degrees
+    pub fn new(
+        elevation: f32,
+        azimuth: f32,
+        turbidity: f32,
+        sun_diameter: f32,
+        intensity: f32,
+    ) -> Self {
+        let (elevation, azimuth) = (elevation.to_radians(), azimuth.to_radians());
+        let sun_direction = Vec3::new(
+            azimuth.sin() * elevation.cos(),
+            elevation.sin(),
+            -azimuth.cos() * elevation.cos(),
+        );
+        let theta_sun = PI / 2.0 - elevation;
+
+        let perez = [
+            Perez::new(
+                turbidity,
+                [
+                    [0.1787, -1.4630],
+                    [-0.3554, 0.4275],
+                    [-0.0227, 5.3251],
+                    [0.1206, -2.5771],
+                    [-0.0670, 0.3703],
+                ],
+            ),
+            Perez::new(
+                turbidity,
+                [
+                    [-0.0193, -0.2592],
+                    [-0.0665, 0.0008],
+                    [-0.0004, 0.2125],
+                    [-0.0641, -0.8989],
+                    [-0.0033, 0.0452],
+                ],
+            ),
+            Perez::new(
+                turbidity,
+                [
+                    [-0.0167, -0.2608],
+                    [-0.0950, 0.0092],
+                    [-0.0079, 0.2102],
+                    [-0.0441, -1.6537],
+                    [-0.0109, 0.0529],
+                ],
+            ),
+        ];
+
+        let chi = (4.0 / 9.0 - turbidity / 120.0) * (PI - 2.0 * theta_sun);
+        let zenith_luminance =
+            (4.0453 * turbidity - 4.9710) * chi.tan() - 0.2155 * turbidity + 2.4192;
+        let chromaticity = |m: [[f32; 4]; 3]| {
+            let t = [theta_sun.powi(3), theta_sun.powi(2), theta_sun, 1.0];
+            let row = |r: [f32; 4]| r.iter().zip(t).map(|(a, b)| a * b).sum::<f32>();
+            turbidity * turbidity * row(m[0]) + turbidity * row(m[1]) + row(m[2])
+        };
+        let zenith_x = chromaticity([
+            [0.00166, -0.00375, 0.00209, 0.0],
+            [-0.02903, 0.06377, -0.03202, 0.00394],
+            [0.11693, -0.21196, 0.06052, 0.25886],
+        ]);
+        let zenith_y = chromaticity([
+            [0.00275, -0.00610, 0.00317, 0.0],
+            [-0.04214, 0.08970, -0.04153, 0.00516],
+            [0.15346, -0.26756, 0.06670, 0.26688],
+        ]);
+
+        let zenith = [zenith_luminance, zenith_x, zenith_y];
+        let zenith = std::array::from_fn(|i| zenith[i] / perez[i].f(0.0, theta_sun));
+
+        // Rayleigh and Ångström aerosol extinction along the air mass of Kasten and Young
+        let air_mass =
+            1.0 / (theta_sun.cos() + 0.50572 * (96.07995 - theta_sun.to_degrees()).powf(-1.6364));
+        let beta = 0.04608 * turbidity - 0.04586;
+        let transmittance = WAVELENGTHS.map(|lambda| {
+            let rayleigh = 0.008735 * lambda.powf(-4.08);
+            let aerosol = beta * lambda.powf(-1.3);
+            (-(rayleigh + aerosol) * air_mass).exp()
+        });
+
+        let sun_radius = (sun_diameter / 2.0).to_radians();
+        let sun_cos_radius = sun_radius.cos();
+        let sun_solid_angle = 2.0 * PI * (1.0 - sun_cos_radius);
+        let sun_radiance = Colour::from(transmittance).scale(SOLAR_ILLUMINANCE / sun_solid_angle);
+
+        let mut sky = Self {
+            sun_direction,
+            sun_cos_radius,
+            sun_radiance,
+            perez,
+            zenith,
+            intensity,
+            sun_probability: 0.5,
+        };
+
+        // Split light samples by how much each part contributes to a surface facing up
+        let sun_power = luminance(sun_radiance) * sun_solid_angle * elevation.sin();
+        let sky_power = luminance(sky.sky_radiance(&Vec3::new(0.0, 1.0, 0.0))) * PI;
+        sky.sun_probability = (sun_power / (sun_power + sky_power)).clamp(0.1, 0.9);
+        sky
+    }
+
+    /// Sky without the sun disc, already scaled by `intensity`
+    fn sky_radiance(&self, direction: &Vec3) -> Colour {
+        let theta = direction.y().clamp(0.0, 1.0).acos();
+        let gamma = direction.dot(&self.sun_direction).clamp(-1.0, 1.0).acos();
+        let [luminance, x, y] =
+            std::array::from_fn(|i| self.zenith[i] * self.perez[i].f(theta, gamma));
+
+        // Yxy to XYZ to linear sRGB
+        let luminance = luminance * LUMINANCE_SCALE;
+        let big_x = x * luminance / y;
+        let big_z = (1.0 - x - y) * luminance / y;
+        Colour::new(
+            3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z,
+            -0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z,
+            0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z,
+        )
+        .scale(self.intensity)
+    }
+
+    fn in_sun(&self, direction: &Vec3) -> bool {
+        direction.y() > 0.0 && direction.dot(&self.sun_direction) >= self.sun_cos_radius
+    }
+
+    fn sun_pdf(&self) -> f32 {
+        1.0 / (2.0 * PI * (1.0 - self.sun_cos_radius))
+    }
+}
+
+fn luminance(c: Colour) -> f32 {
+    0.2126 * c.x() + 0.7152 * c.y() + 0.0722 * c.z()
+}
+
+impl Environment for PhysicalSky {
+    fn radiance(&self, direction: &Vec3) -> Colour {
+        let direction = direction.unit_vector();
+        let sky = self.sky_radiance(&direction);
+        if self.in_sun(&direction) {
+            sky + self.sun_radiance.scale(self.intensity)
+        } else {
+            sky
+        }
+    }
+
+    fn is_sampled(&self) -> bool {
+        true
+    }
+
+    // The sun cone mixed with a cosine lobe around straight up for the sky
+    fn pdf_value(&self, direction: &Vec3) -> f32 {
+        let direction = direction.unit_vector();
+        let sky = CosinePdf::new(&Vec3::new(0.0, 1.0, 0.0)).value(&direction);
+        let sun = if self.in_sun(&direction) {
+            self.sun_pdf()
+        } else {
+            0.0
+        };
+        self.sun_probability * sun + (1.0 - self.sun_probability) * sky
+    }
+
+    fn sample(&self, sampler: &mut Sampler) -> Vec3 {
+        if sampler.random_float() < self.sun_probability {
+            let sun_radius = self.sun_cos_radius.acos();
+            let uvw = Onb::new(&self.sun_direction);
+            uvw.transform(&Vec3::random_to_sphere(sampler, sun_radius.sin(), 1.0))
+        } else {
+            CosinePdf::new(&Vec3::new(0.0, 1.0, 0.0)).generate(sampler)
+        }
+    }
+}
+
+/// Sun elevation and azimuth in degrees, azimuth clockwise from north, for a place and time
+///
+/// Latitude is positive north and longitude positive east, `day` is the day of the year from 1
+/// and `hour` the local clock time in a zone `utc_offset` hours ahead of UTC. Solar time and
+/// declination follow Preetham et al.
+pub fn sun_position(
+    latitude: f32,
+    longitude: f32,
+    day: f32,
+    hour: f32,
+    utc_offset: f32,
+) -> (f32, f32) {
+    let latitude = latitude.to_radians();
+    let standard_meridian = (15.0 * utc_offset).to_radians();
+
+    let solar_time = hour + 0.170 * (4.0 * PI * (day - 80.0) / 373.0).sin()
+        - 0.129 * (2.0 * PI * (day - 8.0) / 355.0).sin()
+        + 12.0 * (longitude.to_radians() - standard_meridian) / PI;
+    let declination = 0.4093 * (2.0 * PI * (day - 81.0) / 368.0).sin();
+    // Angle from local midnight rather than noon, which flips the signs of the usual formulas
+    let hour_angle = PI * solar_time / 12.0;
+
+    let elevation = (latitude.sin() * declination.sin()
+        - latitude.cos() * declination.cos() * hour_angle.cos())
+    .asin();
+    let azimuth = (declination.cos() * hour_angle.sin()).atan2(
+        latitude.cos() * declination.sin() + latitude.sin() * declination.cos() * hour_angle.cos(),
+    );
+
+    (
+        elevation.to_degrees(),
+        azimuth.to_degrees().rem_euclid(360.0),
+    )
+}
@@ -1,12 +1,23 @@
-use std::fs::File;
-use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
 
 use crate::{
-    utils::{interval::Interval, random_float},
-    write_colour, Colour, HitRecord, Hittable, Point3, Ray, Vec3,
+    hittable::{
+        material::{Material, ScatterRecord},
+        pdf::Pdf,
+        HittableList,
+    },
+    scene::background::{Background, Environment},
+    utils::{
+        framebuffer::Framebuffer,
+        interval::Interval,
+        sampler::Sampler,
+        spectrum::{SpectralFilm, Wavelengths},
+    },
+    Colour, HitRecord, Hittable, Point3, Ray, Vec3,
 };
 
-#[derive(Default)]
 pub struct Camera {
     pub aspect_ratio: f32,
     pub image_width: usize,
@@ -18,6 +29,18 @@ pub struct Camera {
     pub vup: Vec3,
     pub defocus_angle: f32,
     pub focus_dist: f32,
+    /// Interval the shutter stays open for, rays are spread evenly over it so moving objects
+    /// blur. Equal times take an instantaneous exposure
+    pub shutter_open: f32,
+    pub shutter_close: f32,
+    pub background: Arc<dyn Environment>,
+    /// Trace a few wavelengths per path instead of RGB, so glass with a varying index of
+    /// refraction disperses light
+    pub spectral: bool,
+    /// Number of worker threads, 0 uses every available core
+    pub threads: usize,
+    /// Seed for every per-pixel sample stream
+    pub seed: u64,
     image_height: usize,
     center: Point3,
     pixel00_loc: Point3,
@@ -28,56 +51,244 @@ pub struct Camera {
     w: Vec3,
     defocus_disk_u: Vec3,
     defocus_disk_v: Vec3,
+    film: Option<SpectralFilm>,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            aspect_ratio: 0.0,
+            image_width: 0,
+            samples_per_pixel: 0,
+            max_depth: 0,
+            vfov: 0.0,
+            lookfrom: Point3::default(),
+            lookat: Point3::default(),
+            vup: Vec3::default(),
+            defocus_angle: 0.0,
+            focus_dist: 0.0,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+            background: Arc::new(Background::default()),
+            spectral: false,
+            threads: 0,
+            seed: 0,
+            image_height: 0,
+            center: Point3::default(),
+            pixel00_loc: Point3::default(),
+            pixel_delta_u: Vec3::default(),
+            pixel_delta_v: Vec3::default(),
+            u: Vec3::default(),
+            v: Vec3::default(),
+            w: Vec3::default(),
+            defocus_disk_u: Vec3::default(),
+            defocus_disk_v: Vec3::default(),
+            film: None,
+        }
+    }
+}
+
+/// Everything a ray can run into while it is traced
+struct World<'a> {
+    surfaces: &'a dyn Hittable,
+    lights: &'a HittableList,
+    media: &'a HittableList,
 }
 
 impl Camera {
-    fn ray_colour(r: &Ray, max_depth: usize, world: &mut dyn Hittable) -> Colour {
-        let mut rec = HitRecord::default();
+    /// Path traces `r`, sampling `lights` and the background at every diffuse bounce and
+    /// weighting both light and BSDF samples with the power heuristic so that neither strategy
+    /// counts light twice
+    ///
+    /// A ray carrying a wavelength is traced spectrally, every colour the scene gives back is
+    /// upsampled to the path's wavelengths and the result holds one radiance per wavelength.
+    fn ray_colour(&self, r: &Ray, world: &World, sampler: &mut Sampler) -> Colour {
+        let lights = world.lights;
+        let wavelength = r.wavelength();
+        // Set once a dispersive surface has left only the hero wavelength
+        let mut hero_only = false;
+        let mut colour = Colour::default();
+        let mut throughput = Colour::new(1.0, 1.0, 1.0);
+        let mut ray = *r;
+        // Density of the BSDF sample that produced `ray`, None after camera and specular rays
+        let mut bsdf_pdf: Option<f32> = None;
 
-        if max_depth <= 0 {
-            return Colour::new(0.0, 0.0, 0.0);
-        }
+        for _ in 0..self.max_depth {
+            let mut rec = HitRecord::default();
+            let hit_surface =
+                world
+                    .surfaces
+                    .hit(&ray, Interval::new(0.001, f32::INFINITY), &mut rec);
+            // A collision inside a medium in front of the surface takes its place
+            let t_max = if hit_surface { rec.t } else { f32::INFINITY };
+            let in_medium =
+                world
+                    .media
+                    .collide(&ray, Interval::new(0.001, t_max), &mut rec, sampler);
 
-        if world.hit(r, Interval::new(0.001, f32::INFINITY), &mut rec) {
-            let mut scattered = Ray::default();
-            let mut attenuation = Colour::default();
+            if !hit_surface && !in_medium {
+                let weight = match bsdf_pdf {
+                    Some(pdf) => power_heuristic(pdf, self.light_pdf(lights, &ray)),
+                    None => 1.0,
+                };
+                let radiance = upsample(&ray, self.background.radiance(ray.direction()));
+                colour = colour + throughput * radiance.scale(weight);
+                break;
+            }
 
-            if rec.mat.scatter(r, &rec, &mut attenuation, &mut scattered) {
-                return attenuation * Self::ray_colour(&scattered, max_depth - 1, world);
+            // Glowing media are never sampled as lights, so their emission counts in full
+            let emitted = upsample(&ray, rec.mat.emitted(rec.u, rec.v, &rec.p));
+            let weight = match bsdf_pdf {
+                Some(pdf) if !in_medium => power_heuristic(pdf, self.light_pdf(lights, &ray)),
+                _ => 1.0,
+            };
+            colour = colour + throughput * emitted.scale(weight);
+
+            let pdf = match rec.mat.scatter(&ray, &rec, sampler) {
+                None => break,
+                Some(ScatterRecord::Specular {
+                    attenuation,
+                    ray: scattered,
+                }) => {
+                    throughput = throughput * material_colour(&ray, &*rec.mat, attenuation);
+                    if wavelength.is_some() && !hero_only && rec.mat.dispersive() {
+                        // The other wavelengths would have gone another way, the hero carries
+                        // on for all three
+                        throughput = throughput * Colour::new(3.0, 0.0, 0.0);
+                        hero_only = true;
+                    }
+                    ray = scattered.with_wavelength(wavelength);
+                    bsdf_pdf = None;
+                    continue;
+                }
+                Some(ScatterRecord::Sampled { pdf }) => pdf,
+            };
+
+            if !lights.objects.is_empty() || self.background.is_sampled() {
+                colour = colour + throughput * self.sample_light(&ray, &rec, &*pdf, world, sampler);
+            }
+
+            let direction = pdf.generate(sampler);
+            let pdf_value = pdf.value(&direction);
+            if pdf_value <= 0.0 {
+                break;
             }
 
+            let f = material_colour(&ray, &*rec.mat, rec.mat.eval(&ray, &rec, &direction));
+            throughput = throughput * f.scale(1.0 / pdf_value);
+            ray = Ray::new(rec.p, direction, ray.time()).with_wavelength(wavelength);
+            bsdf_pdf = Some(pdf_value);
+        }
+
+        colour
+    }
+
+    // Chance of aiming a direct light sample at the background rather than at `lights`
+    fn environment_probability(&self, lights: &HittableList) -> f32 {
+        match (self.background.is_sampled(), lights.objects.is_empty()) {
+            (false, _) => 0.0,
+            (true, true) => 1.0,
+            (true, false) => 0.5,
+        }
+    }
+
+    /// Density of `sample_light` choosing the direction of `r` from its origin
+    fn light_pdf(&self, lights: &HittableList, r: &Ray) -> f32 {
+        let p_environment = self.environment_probability(lights);
+        let mut pdf = 0.0;
+        if p_environment > 0.0 {
+            pdf += p_environment * self.background.pdf_value(r.direction());
+        }
+        if p_environment < 1.0 {
+            pdf += (1.0 - p_environment) * lights.pdf_value(r.origin(), r.direction());
+        }
+        pdf
+    }
+
+    /// Direct light reaching `rec` through one sample towards `lights` or the background
+    fn sample_light(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        bsdf: &dyn Pdf,
+        world: &World,
+        sampler: &mut Sampler,
+    ) -> Colour {
+        let lights = world.lights;
+        let direction = if sampler.random_float() < self.environment_probability(lights) {
+            self.background.sample(sampler)
+        } else {
+            lights.random(&rec.p, sampler)
+        };
+        let shadow_ray = Ray::new(rec.p, direction, r_in.time());
+        let light_pdf = self.light_pdf(lights, &shadow_ray);
+        if light_pdf <= 0.0 {
+            return Colour::default();
+        }
+
+        let f = material_colour(r_in, &*rec.mat, rec.mat.eval(r_in, rec, &direction));
+        if f.near_zero() {
             return Colour::default();
         }
-        let unit_direction = r.direction().unit_vector();
-        let a = 0.5 * (unit_direction.y() + 1.0);
-        Colour::new(1.0, 1.0, 1.0).scale(1.0 - a) + Colour::new(0.5, 0.7, 1.0).scale(a)
+
+        // Whatever surface the shadow ray reaches first is the light that arrives, occluders
+        // included, dimmed by any media on the way
+        let mut light_rec = HitRecord::default();
+        let (emitted, t_light) = if world.surfaces.hit(
+            &shadow_ray,
+            Interval::new(0.001, f32::INFINITY),
+            &mut light_rec,
+        ) {
+            let emitted = light_rec
+                .mat
+                .emitted(light_rec.u, light_rec.v, &light_rec.p);
+            (emitted, light_rec.t)
+        } else {
+            (self.background.radiance(&direction), f32::INFINITY)
+        };
+        if emitted.near_zero() {
+            return Colour::default();
+        }
+        let emitted = upsample(r_in, emitted);
+
+        let transmittance =
+            world
+                .media
+                .transmittance(&shadow_ray, Interval::new(0.001, t_light), sampler);
+        let weight = power_heuristic(light_pdf, bsdf.value(&direction));
+        (f * emitted).scale(transmittance * weight / light_pdf)
     }
 
-    fn defocus_disk_sample(&self) -> Point3 {
-        let p = Vec3::random_in_unit_disk();
+    fn defocus_disk_sample(&self, sampler: &mut Sampler) -> Point3 {
+        let p = Vec3::random_in_unit_disk(sampler);
 
         self.center + (self.defocus_disk_u.scale(p.x()) + self.defocus_disk_v.scale(p.y()))
     }
 
-    fn get_ray(&self, i: usize, j: usize) -> Ray {
+    fn get_ray(&self, i: usize, j: usize, sampler: &mut Sampler) -> Ray {
         let pixel_center = self.pixel00_loc
             + self.pixel_delta_u.scale(i as f32)
             + self.pixel_delta_v.scale(j as f32);
-        let pixel_sample = pixel_center + self.pixel_sample_square();
+        let pixel_sample = pixel_center + self.pixel_sample_square(sampler);
 
         let ray_origin = if self.defocus_angle <= 0.0 {
             self.center
         } else {
-            self.defocus_disk_sample()
+            self.defocus_disk_sample(sampler)
         };
         let ray_direction = pixel_sample - ray_origin;
+        let ray_time = if self.shutter_close > self.shutter_open {
+            sampler.random_float_range(self.shutter_open, self.shutter_close)
+        } else {
+            self.shutter_open
+        };
 
-        Ray::new(ray_origin, ray_direction)
+        Ray::new(ray_origin, ray_direction, ray_time)
     }
 
-    fn pixel_sample_square(&self) -> Vec3 {
-        let px = -0.5 + random_float();
-        let py = -0.5 + random_float();
+    fn pixel_sample_square(&self, sampler: &mut Sampler) -> Vec3 {
+        let px = -0.5 + sampler.random_float();
+        let py = -0.5 + sampler.random_float();
 
         self.pixel_delta_u.scale(px) + self.pixel_delta_v.scale(py)
     }
@@ -116,24 +327,192 @@ impl Camera {
 
         let defocus_radius = self.focus_dist * (self.defocus_angle / 2.0).to_radians().tan();
         self.defocus_disk_u = self.u.scale(defocus_radius);
-        self.defocus_disk_v = self.v.scale(defocus_radius)
-    }
+        self.defocus_disk_v = self.v.scale(defocus_radius);
 
-    pub fn render(&mut self, world: &mut dyn Hittable) {
-        self.initialize();
+        self.film = self.spectral.then(SpectralFilm::default);
+    }
 
-        let mut file = File::create("./out.ppm").unwrap();
-        file.write_all(format!("P3\n{} {}\n255\n", self.image_width, self.image_height).as_bytes())
-            .unwrap();
+    fn thread_count(&self) -> usize {
+        if self.threads > 0 {
+            self.threads
+        } else {
+            thread::available_parallelism().map_or(1, |n| n.get())
+        }
+    }
 
-        for j in 0..self.image_height {
-            for i in 0..self.image_width {
+    fn render_scanline(&self, j: usize, world: &World) -> Vec<Colour> {
+        (0..self.image_width)
+            .map(|i| {
+                let mut sampler = Sampler::new(self.seed, (j * self.image_width + i) as u64);
                 let mut pixel_colour = Colour::new(0.0, 0.0, 0.0);
                 for _ in 0..self.samples_per_pixel {
-                    let r = self.get_ray(i, j);
-                    pixel_colour = pixel_colour + Self::ray_colour(&r, self.max_depth, world);
+                    let r = self.get_ray(i, j, &mut sampler);
+                    let sample = match &self.film {
+                        Some(film) => {
+                            let wavelengths = Wavelengths::sample(&mut sampler);
+                            let r = r.with_wavelength(Some(wavelengths.hero()));
+                            film.to_rgb(&wavelengths, &self.ray_colour(&r, world, &mut sampler))
+                        }
+                        None => self.ray_colour(&r, world, &mut sampler),
+                    };
+                    pixel_colour = pixel_colour + sample;
+                }
+                pixel_colour.scale(1.0 / self.samples_per_pixel as f32)
+            })
+            .collect()
+    }
+
+    /// Renders `world` into a framebuffer of per-pixel averaged linear radiance, `lights` holds
+    /// the emitters to sample directly and `media` the participating media, both may be empty
+    pub fn render(
+        &mut self,
+        world: &dyn Hittable,
+        lights: &HittableList,
+        media: &HittableList,
+    ) -> Framebuffer {
+        self.initialize();
+        let world = World {
+            surfaces: world,
+            lights,
+            media,
+        };
+        let world = &world;
+
+        // Scanlines are handed out one at a time so faster threads pick up the slack
+        let next_scanline = AtomicUsize::new(0);
+        let mut framebuffer = Framebuffer::new(self.image_width, self.image_height);
+
+        let camera = &*self;
+        thread::scope(|s| {
+            let workers: Vec<_> = (0..camera.thread_count())
+                .map(|_| {
+                    s.spawn(|| {
+                        let mut scanlines = Vec::new();
+                        loop {
+                            let j = next_scanline.fetch_add(1, Ordering::Relaxed);
+                            if j >= camera.image_height {
+                                break;
+                            }
+                            scanlines.push((j, camera.render_scanline(j, world)));
+                        }
+                        scanlines
+                    })
+                })
+                .collect();
+
+            for worker in workers {
+                for (j, scanline) in worker.join().unwrap() {
+                    framebuffer.row_mut(j).copy_from_slice(&scanline);
                 }
-                write_colour(&mut file, pixel_colour, self.samples_per_pixel)
+            }
+        });
+
+        framebuffer
+    }
+}
+
+/// Spectral samples of `colour` at the wavelengths `r` carries, or `colour` itself in RGB
+fn upsample(r: &Ray, colour: Colour) -> Colour {
+    match r.wavelength() {
+        Some(hero) => Wavelengths::new(hero).upsample(&colour),
+        None => colour,
+    }
+}
+
+/// Spectral samples of a colour `mat` returned for `r`, unless it already gave them
+fn material_colour(r: &Ray, mat: &dyn Material, colour: Colour) -> Colour {
+    if mat.spectral() {
+        colour
+    } else {
+        upsample(r, colour)
+    }
+}
+
+// Weight of a sample drawn with density `f` when `g` is the density of the other strategy
+fn power_heuristic(f: f32, g: f32) -> f32 {
+    let (f2, g2) = (f * f, g * g);
+    if f2 + g2 > 0.0 {
+        f2 / (f2 + g2)
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        hittable::{
+            material::{
+                dielectric::Dielectric, diffuse_light::DiffuseLight, lambertian::Lambertian,
+                metal::Metal,
+            },
+            sphere::Sphere,
+            HittableList,
+        },
+        Colour, Point3, Vec3,
+    };
+
+    use super::*;
+
+    fn render(threads: usize) -> Vec<Colour> {
+        let mut world = HittableList::new();
+        world.add(Box::new(Sphere::new(
+            Point3::new(0.0, -1000.0, 0.0),
+            1000.0,
+            Arc::new(Lambertian::new(Colour::new(0.5, 0.5, 0.5))),
+        )));
+        world.add(Box::new(Sphere::new(
+            Point3::new(-1.0, 1.0, 0.0),
+            1.0,
+            Arc::new(Dielectric::new(1.5)),
+        )));
+        world.add(Box::new(Sphere::new(
+            Point3::new(1.0, 1.0, 0.0),
+            1.0,
+            Arc::new(Metal::new(Colour::new(0.7, 0.6, 0.5), 0.2)),
+        )));
+        let light = || {
+            Box::new(Sphere::new(
+                Point3::new(0.0, 4.0, 1.0),
+                0.5,
+                Arc::new(DiffuseLight::new(Colour::new(8.0, 8.0, 8.0))),
+            ))
+        };
+        world.add(light());
+        let mut lights = HittableList::new();
+        lights.add(light());
+
+        let mut cam = Camera {
+            aspect_ratio: 1.5,
+            image_width: 24,
+            samples_per_pixel: 4,
+            max_depth: 8,
+            vfov: 40.0,
+            lookfrom: Point3::new(0.0, 2.0, 6.0),
+            lookat: Point3::new(0.0, 1.0, 0.0),
+            vup: Vec3::new(0.0, 1.0, 0.0),
+            defocus_angle: 0.6,
+            focus_dist: 6.0,
+            threads,
+            seed: 42,
+            ..Default::default()
+        };
+        cam.render(&world, &lights, &HittableList::new()).pixels
+    }
+
+    #[test]
+    fn same_seed_renders_identically_on_any_thread_count() {
+        let single = render(1);
+        for threads in [2, 3, 8] {
+            let pixels = render(threads);
+            assert_eq!(single.len(), pixels.len());
+            for (i, (a, b)) in single.iter().zip(&pixels).enumerate() {
+                assert!(
+                    a.x() == b.x() && a.y() == b.y() && a.z() == b.z(),
+                    "pixel {i} differs with {threads} threads"
+                );
             }
         }
     }
@@ -0,0 +1,408 @@
+pub mod background;
+pub mod material;
+pub mod object;
+pub mod sdf;
+pub mod texture;
+pub mod transform;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::Deserialize;
+use toml::Spanned;
+
+use crate::{
+    hittable::{Hittable, HittableList},
+    Camera, Point3, Vec3,
+};
+
+use super::{Scene, SceneError};
+
+use self::{
+    background::BackgroundDescription,
+    material::MaterialDescription,
+    object::{Geometry, ObjectBuilder, ObjectDescription, SortedObjects},
+    texture::{TextureDescription, TextureRef, TextureResolver},
+};
+
+/// Top level of a scene file
+///
+/// ```toml
+/// [camera]
+/// lookfrom = [13.0, 2.0, 3.0]
+///
+/// [textures.checker]
+/// type = "checker"
+/// scale = 0.32
+/// even = [0.2, 0.3, 0.1]
+/// odd = [0.9, 0.9, 0.9]
+///
+/// [materials.ground]
+/// type = "lambertian"
+/// albedo = "checker"
+///
+/// [[objects]]
+/// type = "sphere"
+/// center = [0.0, -1000.0, 0.0]
+/// radius = 1000.0
+/// material = "ground"
+/// ```
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SceneDescription {
+    pub camera: Spanned<CameraDescription>,
+    pub textures: BTreeMap<String, Spanned<TextureDescription>>,
+    pub materials: BTreeMap<String, Spanned<MaterialDescription>>,
+    /// Named lists of objects that are built once and placed by `instance` objects
+    pub geometry: BTreeMap<String, Vec<Spanned<ObjectDescription>>>,
+    pub objects: Vec<Spanned<ObjectDescription>>,
+}
+
+impl Default for SceneDescription {
+    fn default() -> Self {
+        Self {
+            camera: Spanned::new(0..0, CameraDescription::default()),
+            textures: BTreeMap::new(),
+            materials: BTreeMap::new(),
+            geometry: BTreeMap::new(),
+            objects: Vec::new(),
+        }
+    }
+}
+
+/// Mirrors the public fields of `Camera`, anything left out keeps its default
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct CameraDescription {
+    pub aspect_ratio: f32,
+    pub image_width: usize,
+    pub samples_per_pixel: usize,
+    pub max_depth: usize,
+    pub vfov: f32,
+    pub lookfrom: [f32; 3],
+    pub lookat: [f32; 3],
+    pub vup: [f32; 3],
+    pub defocus_angle: f32,
+    pub focus_dist: f32,
+    pub shutter_open: f32,
+    pub shutter_close: f32,
+    pub background: BackgroundDescription,
+    pub spectral: bool,
+    pub threads: usize,
+    pub seed: u64,
+}
+
+impl Default for CameraDescription {
+    fn default() -> Self {
+        Self {
+            aspect_ratio: 16.0 / 9.0,
+            image_width: 400,
+            samples_per_pixel: 100,
+            max_depth: 50,
+            vfov: 90.0,
+            lookfrom: [0.0, 0.0, 0.0],
+            lookat: [0.0, 0.0, -1.0],
+            vup: [0.0, 1.0, 0.0],
+            defocus_angle: 0.0,
+            focus_dist: 1.0,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+            background: BackgroundDescription::default(),
+            spectral: false,
+            threads: 0,
+            seed: 0,
+        }
+    }
+}
+
+/// Camera settings given outside the scene file, each one replaces the scene's value
+#[derive(Default)]
+pub struct CameraOverrides {
+    pub image_width: Option<usize>,
+    /// Adjusts the aspect ratio to match the width, or the width to match the aspect ratio
+    pub image_height: Option<usize>,
+    pub samples_per_pixel: Option<usize>,
+    pub max_depth: Option<usize>,
+    pub threads: Option<usize>,
+    pub seed: Option<u64>,
+    pub lookfrom: Option<[f32; 3]>,
+    pub lookat: Option<[f32; 3]>,
+    pub vfov: Option<f32>,
+    pub defocus_angle: Option<f32>,
+    pub focus_dist: Option<f32>,
+    /// Turns spectral rendering on, it cannot turn it off
+    pub spectral: bool,
+}
+
+fn line_of(source: &str, span: Range<usize>) -> Option<usize> {
+    source
+        .get(..span.start)
+        .map(|before| before.matches('\n').count() + 1)
+}
+
+fn is_positive(x: f32) -> bool {
+    x > 0.0 && x.is_finite()
+}
+
+impl CameraDescription {
+    fn validate(&self) -> Result<(), String> {
+        if self.image_width == 0 {
+            return Err("camera.image_width must be at least 1".to_string());
+        }
+        if self.samples_per_pixel == 0 {
+            return Err("camera.samples_per_pixel must be at least 1".to_string());
+        }
+        if !is_positive(self.aspect_ratio) {
+            return Err("camera.aspect_ratio must be positive".to_string());
+        }
+        if !(self.vfov > 0.0 && self.vfov < 180.0) {
+            return Err("camera.vfov must be between 0 and 180 degrees".to_string());
+        }
+        if !is_positive(self.focus_dist) {
+            return Err("camera.focus_dist must be positive".to_string());
+        }
+        let direction = Point3::from(self.lookat) - Point3::from(self.lookfrom);
+        if !is_positive(direction.length()) {
+            return Err("camera.lookat must be a finite point away from lookfrom".to_string());
+        }
+        // Sine of the angle between vup and the view direction, the camera cannot tell which
+        // way is up when they are parallel
+        let vup = Vec3::from(self.vup);
+        let sin = vup.cross(&direction).length() / (vup.length() * direction.length());
+        if sin.is_nan() || sin < 1e-4 {
+            return Err(
+                "camera.vup must not be zero or parallel to the view direction".to_string(),
+            );
+        }
+        if !self.shutter_open.is_finite() || !self.shutter_close.is_finite() {
+            return Err("camera.shutter_open and shutter_close must be finite".to_string());
+        }
+        if self.shutter_close < self.shutter_open {
+            return Err("camera.shutter_close cannot come before shutter_open".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn apply(&mut self, overrides: &CameraOverrides) {
+        if let Some(width) = overrides.image_width {
+            self.image_width = width;
+        }
+        if let Some(height) = overrides.image_height {
+            if overrides.image_width.is_some() {
+                self.aspect_ratio = self.image_width as f32 / height as f32;
+            } else {
+                self.image_width = ((height as f32 * self.aspect_ratio).round() as usize).max(1);
+            }
+        }
+
+        self.samples_per_pixel = overrides
+            .samples_per_pixel
+            .unwrap_or(self.samples_per_pixel);
+        self.max_depth = overrides.max_depth.unwrap_or(self.max_depth);
+        self.threads = overrides.threads.unwrap_or(self.threads);
+        self.seed = overrides.seed.unwrap_or(self.seed);
+        self.lookfrom = overrides.lookfrom.unwrap_or(self.lookfrom);
+        self.lookat = overrides.lookat.unwrap_or(self.lookat);
+        self.vfov = overrides.vfov.unwrap_or(self.vfov);
+        self.defocus_angle = overrides.defocus_angle.unwrap_or(self.defocus_angle);
+        self.focus_dist = overrides.focus_dist.unwrap_or(self.focus_dist);
+        self.spectral |= overrides.spectral;
+    }
+
+    /// `base_dir` resolves a relative environment map path
+    pub fn build(&self, base_dir: &Path) -> Result<Camera, String> {
+        let mut cam = Camera::default();
+
+        cam.aspect_ratio = self.aspect_ratio;
+        cam.image_width = self.image_width;
+        cam.samples_per_pixel = self.samples_per_pixel;
+        cam.max_depth = self.max_depth;
+        cam.vfov = self.vfov;
+        cam.lookfrom = Point3::from(self.lookfrom);
+        cam.lookat = Point3::from(self.lookat);
+        cam.vup = Point3::from(self.vup);
+        cam.defocus_angle = self.defocus_angle;
+        cam.focus_dist = self.focus_dist;
+        cam.shutter_open = self.shutter_open;
+        cam.shutter_close = self.shutter_close;
+        cam.background = self.background.build(base_dir)?;
+        cam.spectral = self.spectral;
+        cam.threads = self.threads;
+        cam.seed = self.seed;
+
+        Ok(cam)
+    }
+}
+
+impl SceneDescription {
+    pub fn from_toml(source: &str) -> Result<Self, SceneError> {
+        toml::from_str(source).map_err(|e| SceneError {
+            line: e.span().and_then(|span| line_of(source, span)),
+            message: e.message().to_string(),
+        })
+    }
+
+    /// Builds the camera and world, `source` is only used to turn spans into line numbers and
+    /// `base_dir` resolves relative mesh and image paths
+    pub fn build(&self, source: &str, base_dir: &Path) -> Result<Scene, SceneError> {
+        let error_at = |span: Range<usize>, message: String| SceneError {
+            line: line_of(source, span),
+            message,
+        };
+
+        self.camera
+            .get_ref()
+            .validate()
+            .map_err(|message| error_at(self.camera.span(), message))?;
+
+        let mut textures = TextureResolver {
+            descriptions: &self.textures,
+            built: HashMap::new(),
+            in_progress: HashSet::new(),
+            source,
+            base_dir,
+            seed: self.camera.get_ref().seed,
+        };
+
+        // Textures nobody uses still get checked
+        for name in self.textures.keys() {
+            textures.resolve(&TextureRef::Named(name.clone()))?;
+        }
+
+        let mut materials = HashMap::new();
+        for (name, material) in &self.materials {
+            let built = material
+                .get_ref()
+                .build(&mut textures)
+                .map_err(|e| match e.line {
+                    Some(_) => e,
+                    None => error_at(material.span(), format!("{}: {}", name, e.message)),
+                })?;
+            materials.insert(name.as_str(), built);
+        }
+
+        let mut builder = ObjectBuilder {
+            descriptions: &self.materials,
+            materials: &materials,
+            geometry: HashMap::new(),
+            base_dir,
+        };
+
+        for (name, objects) in &self.geometry {
+            let mut lists = SortedObjects::new();
+            for object in objects {
+                if matches!(object.get_ref(), ObjectDescription::Instance { .. }) {
+                    return Err(error_at(
+                        object.span(),
+                        "geometry cannot contain instances".to_string(),
+                    ));
+                }
+                let built = builder
+                    .build(object.get_ref())
+                    .map_err(|message| error_at(object.span(), message))?;
+                lists.add(built);
+            }
+
+            let shared = |list: HittableList| -> Option<Arc<dyn Hittable>> {
+                (!list.objects.is_empty()).then(|| Arc::new(list) as Arc<dyn Hittable>)
+            };
+            let surfaces = (!lists.surfaces.objects.is_empty())
+                .then(|| Arc::new(lists.surfaces.into_bvh()) as Arc<dyn Hittable>);
+            builder.geometry.insert(
+                name.as_str(),
+                Geometry {
+                    surfaces,
+                    lights: shared(lists.lights),
+                    media: shared(lists.media),
+                },
+            );
+        }
+
+        let mut lists = SortedObjects::new();
+        for object in &self.objects {
+            let built = builder
+                .build(object.get_ref())
+                .map_err(|message| error_at(object.span(), message))?;
+            lists.add(built);
+        }
+
+        Ok(Scene {
+            camera: self
+                .camera
+                .get_ref()
+                .build(base_dir)
+                .map_err(|message| error_at(self.camera.span(), message))?,
+            world: lists.surfaces,
+            lights: lists.lights,
+            media: lists.media,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_of(source: &str) -> SceneError {
+        match SceneDescription::from_toml(source).and_then(|d| d.build(source, Path::new("."))) {
+            Ok(_) => panic!("scene loaded without error"),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn syntax_errors_report_their_line() {
+        let e = error_of("[camera]\nimage_width = 400\nvfov = \nmax_depth = 10\n");
+        assert_eq!(e.line, Some(3), "{}", e.message);
+    }
+
+    #[test]
+    fn unknown_fields_report_the_line_of_their_table() {
+        let source = "[camera]\nimage_width = 400\n\n[materials.red]\ntype = \"lambertian\"\ncolour = [1.0, 0.0, 0.0]\n";
+        let e = error_of(source);
+        assert_eq!(e.line, Some(4), "{}", e.message);
+    }
+
+    #[test]
+    fn invalid_values_report_the_line_of_their_table() {
+        let source = "# comment\n\n[camera]\nimage_width = 0\n";
+        let e = error_of(source);
+        assert_eq!(e.line, Some(3), "{}", e.message);
+        assert!(e.message.contains("image_width"), "{}", e.message);
+
+        let source = "[materials.red]\ntype = \"lambertian\"\nalbedo = [1.0, 0.0, 0.0]\n\n[[objects]]\ntype = \"sphere\"\ncenter = [0.0, 0.0, 0.0]\nradius = 1.0\nmaterial = \"blue\"\n";
+        let e = error_of(source);
+        assert_eq!(e.line, Some(5), "{}", e.message);
+        assert!(e.message.contains("blue"), "{}", e.message);
+    }
+
+    #[test]
+    fn sphere_radii_must_be_positive_and_finite() {
+        for radius in ["0.0", "-1.0", "nan", "inf"] {
+            let source = format!(
+                "[materials.red]\ntype = \"lambertian\"\nalbedo = [1.0, 0.0, 0.0]\n\n[[objects]]\ntype = \"sphere\"\ncenter = [0.0, 0.0, 0.0]\nradius = {radius}\nmaterial = \"red\"\n"
+            );
+            let e = error_of(&source);
+            assert_eq!(e.line, Some(5), "{}", e.message);
+            assert!(e.message.contains("radius"), "{}", e.message);
+        }
+    }
+
+    #[test]
+    fn degenerate_camera_orientations_are_rejected() {
+        for camera in [
+            "lookfrom = [1.0, 2.0, 3.0]\nlookat = [1.0, 2.0, 3.0]",
+            "lookfrom = [0.0, 5.0, 0.0]\nlookat = [0.0, 0.0, 0.0]",
+            "lookfrom = [0.0, 0.0, 1.0]\nvup = [0.0, 0.0, 0.0]",
+            "lookfrom = [0.0, 0.0, 1.0]\nlookat = [nan, 0.0, 0.0]",
+            "shutter_open = 0.0\nshutter_close = inf",
+            "shutter_open = nan\nshutter_close = 1.0",
+        ] {
+            let source = format!("# comment\n[camera]\n{camera}\n");
+            let e = error_of(&source);
+            assert_eq!(e.line, Some(2), "{camera}: {}", e.message);
+        }
+    }
+}
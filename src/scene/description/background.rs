@@ -0,0 +1,152 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::{
+    hittable::texture::image::ImageData,
+    scene::background::{
+        environment_map::EnvironmentMap,
+        sky::{sun_position, PhysicalSky},
+        Background, Environment,
+    },
+    Colour,
+};
+
+#[derive(Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum BackgroundDescription {
+    None,
+    Solid {
+        colour: [f32; 3],
+    },
+    Gradient {
+        bottom: [f32; 3],
+        top: [f32; 3],
+    },
+    /// Equirectangular HDR or EXR image relative to the scene file, `rotation` is in degrees
+    EnvironmentMap {
+        path: PathBuf,
+        #[serde(default)]
+        rotation: f32,
+        #[serde(default = "default_intensity")]
+        intensity: f32,
+    },
+    /// Preetham daylight with a sampled sun, `sun_diameter` is in degrees
+    Sky {
+        sun: SunDescription,
+        #[serde(default = "default_turbidity")]
+        turbidity: f32,
+        #[serde(default = "default_sun_diameter")]
+        sun_diameter: f32,
+        #[serde(default = "default_intensity")]
+        intensity: f32,
+    },
+}
+
+fn default_intensity() -> f32 {
+    1.0
+}
+
+fn default_turbidity() -> f32 {
+    3.0
+}
+
+fn default_sun_diameter() -> f32 {
+    0.53
+}
+
+/// Where the sun is, either directly in degrees or from a place and local time
+#[derive(Deserialize, Clone)]
+#[serde(untagged, deny_unknown_fields)]
+pub enum SunDescription {
+    /// Azimuth is clockwise from north (-z) towards east (+x)
+    Angles { elevation: f32, azimuth: f32 },
+    /// Latitude positive north, longitude positive east, `day` of the year and `hour` of the
+    /// local clock in a zone `utc_offset` hours ahead of UTC
+    Location {
+        latitude: f32,
+        longitude: f32,
+        day: f32,
+        hour: f32,
+        #[serde(default)]
+        utc_offset: f32,
+    },
+}
+
+impl Default for BackgroundDescription {
+    fn default() -> Self {
+        Self::Gradient {
+            bottom: [1.0, 1.0, 1.0],
+            top: [0.5, 0.7, 1.0],
+        }
+    }
+}
+
+impl BackgroundDescription {
+    pub(super) fn build(&self, base_dir: &Path) -> Result<Arc<dyn Environment>, String> {
+        match self {
+            Self::None => Ok(Arc::new(Background::None)),
+            Self::Solid { colour } => Ok(Arc::new(Background::Solid(Colour::from(*colour)))),
+            Self::Gradient { bottom, top } => Ok(Arc::new(Background::Gradient {
+                bottom: Colour::from(*bottom),
+                top: Colour::from(*top),
+            })),
+            Self::EnvironmentMap {
+                path,
+                rotation,
+                intensity,
+            } => {
+                if *intensity < 0.0 {
+                    return Err("environment map intensity cannot be negative".to_string());
+                }
+                let path = base_dir.join(path);
+                let image = ImageData::load(&path)
+                    .map_err(|e| format!("cannot load {}: {}", path.display(), e))?;
+                Ok(Arc::new(EnvironmentMap::new(image, *rotation, *intensity)))
+            }
+            Self::Sky {
+                sun,
+                turbidity,
+                sun_diameter,
+                intensity,
+            } => {
+                // The fitted model only covers clear to hazy skies
+                if !(1.7..=10.0).contains(turbidity) {
+                    return Err("sky turbidity must be between 1.7 and 10".to_string());
+                }
+                if !(*sun_diameter > 0.0 && *sun_diameter < 90.0) {
+                    return Err("sky sun_diameter must be between 0 and 90 degrees".to_string());
+                }
+                if *intensity < 0.0 {
+                    return Err("sky intensity cannot be negative".to_string());
+                }
+
+                let (elevation, azimuth) = match *sun {
+                    SunDescription::Angles { elevation, azimuth } => (elevation, azimuth),
+                    SunDescription::Location {
+                        latitude,
+                        longitude,
+                        day,
+                        hour,
+                        utc_offset,
+                    } => sun_position(latitude, longitude, day, hour, utc_offset),
+                };
+                if !(elevation > 0.0 && elevation <= 90.0) {
+                    return Err(format!(
+                        "the sun must be above the horizon, its elevation is {:.1} degrees",
+                        elevation
+                    ));
+                }
+
+                Ok(Arc::new(PhysicalSky::new(
+                    elevation,
+                    azimuth,
+                    *turbidity,
+                    *sun_diameter,
+                    *intensity,
+                )))
+            }
+        }
+    }
+}
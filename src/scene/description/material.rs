@@ -0,0 +1,305 @@
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::{
+    hittable::material::{
+        conductor::{Conductor, NamedMetal},
+        dielectric::{Dielectric, Ior},
+        diffuse_light::DiffuseLight,
+        henyey_greenstein::HenyeyGreenstein,
+        isotropic::Isotropic,
+        lambertian::Lambertian,
+        metal::Metal,
+        principled::Principled,
+        rough_dielectric::RoughDielectric,
+        thin_film::ThinFilm,
+        Material,
+    },
+    scene::SceneError,
+    utils::spectrum::{LAMBDA_MAX, LAMBDA_MIN},
+    Colour,
+};
+
+use super::{
+    is_positive,
+    texture::{ScalarRef, TextureRef, TextureResolver},
+};
+
+/// Index of refraction, constant or varying with wavelength in micrometres
+///
+/// ```toml
+/// ir = 1.5
+/// ir = { cauchy = { a = 1.5046, b = 0.00420 } }
+/// ir = { sellmeier = { b = [1.03961212, 0.231792344, 1.01046945], c = [0.00600069867, 0.0200179144, 103.560653] } }
+/// ```
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+pub enum IorDescription {
+    Constant(f32),
+    Curve(IorCurve),
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum IorCurve {
+    Cauchy { a: f32, b: f32 },
+    Sellmeier { b: [f32; 3], c: [f32; 3] },
+}
+
+impl IorDescription {
+    fn build(&self) -> Result<Ior, String> {
+        let ior = match self {
+            Self::Constant(ir) => Ior::Constant(*ir),
+            Self::Curve(IorCurve::Cauchy { a, b }) => Ior::Cauchy { a: *a, b: *b },
+            Self::Curve(IorCurve::Sellmeier { b, c }) => Ior::Sellmeier { b: *b, c: *c },
+        };
+        // Checked across the visible range, Sellmeier poles must stay outside it
+        let step = 10.0;
+        let mut lambda = LAMBDA_MIN;
+        while lambda <= LAMBDA_MAX {
+            if !is_positive(ior.at(lambda)) {
+                return Err(format!(
+                    "dielectric ir must be positive, not at {} nm",
+                    lambda
+                ));
+            }
+            lambda += step;
+        }
+        Ok(ior)
+    }
+}
+
+/// Thin film over a `metal` or `dielectric`, `thickness` in nanometres is scaled by the mean of
+/// the channels of the optional `texture`
+///
+/// ```toml
+/// coating = { ior = 1.33, thickness = 400, texture = "swirl" }
+/// ```
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct CoatingDescription {
+    pub ior: f32,
+    pub thickness: f32,
+    pub texture: Option<String>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum MaterialDescription {
+    Lambertian {
+        albedo: TextureRef,
+    },
+    Metal {
+        albedo: TextureRef,
+        #[serde(default)]
+        fuzz: f32,
+        coating: Option<CoatingDescription>,
+    },
+    /// `ir` is a number or a dispersion curve, which only matters when rendering spectrally
+    Dielectric {
+        ir: IorDescription,
+        coating: Option<CoatingDescription>,
+    },
+    /// GGX metal, either a named metal or a complex index of refraction `eta` + i`k`
+    Conductor {
+        metal: Option<NamedMetal>,
+        eta: Option<[f32; 3]>,
+        k: Option<[f32; 3]>,
+        #[serde(default)]
+        roughness: RoughnessDescription,
+    },
+    RoughDielectric {
+        ir: f32,
+        #[serde(default)]
+        roughness: RoughnessDescription,
+    },
+    /// Disney-style material, omitted parameters keep the defaults of `Principled`
+    Principled {
+        base_colour: Option<TextureRef>,
+        metallic: Option<ScalarRef>,
+        roughness: Option<ScalarRef>,
+        specular: Option<ScalarRef>,
+        sheen: Option<ScalarRef>,
+        clearcoat: Option<ScalarRef>,
+        transmission: Option<ScalarRef>,
+        ior: Option<f32>,
+    },
+    DiffuseLight {
+        emit: [f32; 3],
+    },
+    /// Phase functions, only meaningful inside a `constant_medium`
+    Isotropic {
+        albedo: TextureRef,
+    },
+    /// `g` between -1 and 1 is the mean cosine of the scattering angle, positive values scatter
+    /// forwards
+    HenyeyGreenstein {
+        albedo: TextureRef,
+        g: f32,
+    },
+}
+
+/// One roughness for both directions, or separate ones along the tangent and bitangent
+#[derive(Deserialize, Clone, Copy)]
+#[serde(untagged)]
+pub enum RoughnessDescription {
+    Isotropic(f32),
+    Anisotropic([f32; 2]),
+}
+
+impl Default for RoughnessDescription {
+    fn default() -> Self {
+        Self::Isotropic(0.0)
+    }
+}
+
+impl RoughnessDescription {
+    fn validate(&self) -> Result<(f32, f32), &'static str> {
+        let (u, v) = match *self {
+            Self::Isotropic(r) => (r, r),
+            Self::Anisotropic([u, v]) => (u, v),
+        };
+        if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+            return Err("roughness must be between 0 and 1");
+        }
+        Ok((u, v))
+    }
+}
+
+impl CoatingDescription {
+    fn build(&self, textures: &mut TextureResolver) -> Result<ThinFilm, SceneError> {
+        let invalid = |message: &str| SceneError {
+            line: None,
+            message: message.to_string(),
+        };
+        if !is_positive(self.ior) {
+            return Err(invalid("coating ior must be positive"));
+        }
+        if !(self.thickness >= 0.0 && self.thickness.is_finite()) {
+            return Err(invalid("coating thickness cannot be negative"));
+        }
+        let texture = self
+            .texture
+            .as_ref()
+            .map(|name| textures.resolve(&TextureRef::Named(name.clone())))
+            .transpose()?;
+
+        Ok(ThinFilm::new(self.ior, self.thickness, texture))
+    }
+}
+
+impl MaterialDescription {
+    pub(super) fn build(
+        &self,
+        textures: &mut TextureResolver,
+    ) -> Result<Arc<dyn Material>, SceneError> {
+        let invalid = |message: &str| SceneError {
+            line: None,
+            message: message.to_string(),
+        };
+
+        match self {
+            Self::Lambertian { albedo } => Ok(Arc::new(Lambertian::from_texture(
+                textures.resolve(albedo)?,
+            ))),
+            Self::Metal {
+                albedo,
+                fuzz,
+                coating,
+            } => {
+                if *fuzz < 0.0 {
+                    return Err(invalid("metal fuzz cannot be negative"));
+                }
+                let metal = Metal::from_texture(textures.resolve(albedo)?, *fuzz);
+                Ok(match coating {
+                    Some(coating) => Arc::new(metal.with_coating(coating.build(textures)?)),
+                    None => Arc::new(metal),
+                })
+            }
+            Self::Dielectric { ir, coating } => {
+                let glass = Dielectric::with_ior(ir.build().map_err(|e| invalid(&e))?);
+                Ok(match coating {
+                    Some(coating) => Arc::new(glass.with_coating(coating.build(textures)?)),
+                    None => Arc::new(glass),
+                })
+            }
+            Self::Conductor {
+                metal,
+                eta,
+                k,
+                roughness,
+            } => {
+                let (u, v) = roughness.validate().map_err(invalid)?;
+                match (metal, eta, k) {
+                    (Some(metal), None, None) => Ok(Arc::new(Conductor::named(*metal, u, v))),
+                    (None, Some(eta), Some(k)) => Ok(Arc::new(Conductor::new(
+                        Colour::from(*eta),
+                        Colour::from(*k),
+                        u,
+                        v,
+                    ))),
+                    _ => Err(invalid("conductor needs either metal or both eta and k")),
+                }
+            }
+            Self::RoughDielectric { ir, roughness } => {
+                if !is_positive(*ir) {
+                    return Err(invalid("rough_dielectric ir must be positive"));
+                }
+                let (u, v) = roughness.validate().map_err(invalid)?;
+                Ok(Arc::new(RoughDielectric::new(*ir, u, v)))
+            }
+            Self::Principled {
+                base_colour,
+                metallic,
+                roughness,
+                specular,
+                sheen,
+                clearcoat,
+                transmission,
+                ior,
+            } => {
+                let mut material = Principled::default();
+                if let Some(base_colour) = base_colour {
+                    material.base_colour = textures.resolve(base_colour)?;
+                }
+
+                let scalars = [
+                    ("metallic", metallic, &mut material.metallic),
+                    ("roughness", roughness, &mut material.roughness),
+                    ("specular", specular, &mut material.specular),
+                    ("sheen", sheen, &mut material.sheen),
+                    ("clearcoat", clearcoat, &mut material.clearcoat),
+                    ("transmission", transmission, &mut material.transmission),
+                ];
+                for (name, value, field) in scalars {
+                    if let Some(value) = value {
+                        *field = textures.resolve_scalar(value).map_err(|e| SceneError {
+                            message: format!("{}: {}", name, e.message),
+                            ..e
+                        })?;
+                    }
+                }
+
+                if let Some(ior) = ior {
+                    if !is_positive(*ior) {
+                        return Err(invalid("principled ior must be positive"));
+                    }
+                    material.ior = *ior;
+                }
+                Ok(Arc::new(material))
+            }
+            Self::DiffuseLight { emit } => Ok(Arc::new(DiffuseLight::new(Colour::from(*emit)))),
+            Self::Isotropic { albedo } => Ok(Arc::new(Isotropic::new(textures.resolve(albedo)?))),
+            Self::HenyeyGreenstein { albedo, g } => {
+                if !(*g > -1.0 && *g < 1.0) {
+                    return Err(invalid("henyey_greenstein g must be between -1 and 1"));
+                }
+                Ok(Arc::new(HenyeyGreenstein::new(
+                    textures.resolve(albedo)?,
+                    *g,
+                )))
+            }
+        }
+    }
+}
@@ -0,0 +1,515 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::Deserialize;
+use toml::Spanned;
+
+use crate::{
+    hittable::{
+        constant_medium::ConstantMedium,
+        csg::{Csg, Operation},
+        grid_medium::{GridMedium, VolumeEmission},
+        instance::Instance,
+        material::Material,
+        quad::{make_box, Quad},
+        sdf::Sdf,
+        sphere::Sphere,
+        transform::{MovingTransform, Transform},
+        triangle::Triangle,
+        voxel_grid::VoxelGrid,
+        Hittable, HittableList,
+    },
+    scene::obj::load_obj,
+    Colour, Point3, Vec3,
+};
+
+use super::{
+    is_positive,
+    material::MaterialDescription,
+    sdf::SdfDescription,
+    transform::{animate, compose, TransformStep},
+};
+
+#[derive(Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ObjectDescription {
+    /// `center1` makes the sphere move in a straight line from `center` over the shutter
+    Sphere {
+        center: [f32; 3],
+        center1: Option<[f32; 3]>,
+        radius: f32,
+        material: String,
+    },
+    /// Parallelogram with corner `q` and edges `u` and `v`
+    Quad {
+        q: [f32; 3],
+        u: [f32; 3],
+        v: [f32; 3],
+        material: String,
+    },
+    Triangle {
+        a: [f32; 3],
+        b: [f32; 3],
+        c: [f32; 3],
+        material: String,
+    },
+    /// Axis-aligned box spanning the corners `a` and `b`
+    Box {
+        a: [f32; 3],
+        b: [f32; 3],
+        material: String,
+    },
+    /// Wavefront OBJ file relative to the scene file, `material` replaces the MTL materials
+    Mesh {
+        path: PathBuf,
+        material: Option<String>,
+    },
+    /// Fog or smoke of uniform `density` filling `boundary`, `material` is its phase function
+    ConstantMedium {
+        boundary: BoundaryDescription,
+        density: f32,
+        material: String,
+    },
+    /// `object` moved by a list of steps applied in order. `transform1`, a list of the same
+    /// kinds of steps, animates it: each parameter moves linearly from its value in
+    /// `transform` at shutter time 0 to its value in `transform1` at time 1
+    ///
+    /// ```toml
+    /// [[objects]]
+    /// type = "transform"
+    /// transform = [{ rotate = { axis = [0, 1, 0], angle = 15 } }, { translate = [265, 0, 295] }]
+    /// object = { type = "box", a = [0, 0, 0], b = [165, 330, 165], material = "white" }
+    /// ```
+    Transform {
+        transform: Vec<TransformStep>,
+        transform1: Option<Vec<TransformStep>>,
+        object: Box<ObjectDescription>,
+    },
+    /// Procedural shape traced through its signed distance function
+    ///
+    /// ```toml
+    /// [[objects]]
+    /// type = "sdf"
+    /// material = "gold"
+    /// shape = { type = "twist", angle = 90, shape = { type = "rounded_box", size = [1, 2, 1], radius = 0.1 } }
+    /// ```
+    Sdf {
+        shape: SdfDescription,
+        material: String,
+    },
+    /// Solid combining the insides of two closed objects, `difference` keeps what is inside `a`
+    /// but not `b`
+    ///
+    /// ```toml
+    /// [[objects]]
+    /// type = "csg"
+    /// operation = "difference"
+    /// a = { type = "sphere", center = [0, 1, 0], radius = 1, material = "red" }
+    /// b = { type = "box", a = [0, 0, 0], b = [2, 2, 2], material = "white" }
+    /// ```
+    Csg {
+        operation: Operation,
+        a: Box<ObjectDescription>,
+        b: Box<ObjectDescription>,
+    },
+    /// Copy of a `[geometry]` entry placed by `transform`, with `material` replacing the
+    /// materials of its surfaces when given
+    ///
+    /// ```toml
+    /// [[geometry.tree]]
+    /// type = "mesh"
+    /// path = "tree.obj"
+    ///
+    /// [[objects]]
+    /// type = "instance"
+    /// geometry = "tree"
+    /// transform = [{ scale = [1.2, 1.2, 1.2] }, { translate = [4, 0, -2] }]
+    /// ```
+    Instance {
+        geometry: String,
+        #[serde(default)]
+        transform: Vec<TransformStep>,
+        material: Option<String>,
+    },
+    /// Smoke, cloud or fire read from a Mitsuba `.vol` density grid relative to the scene file,
+    /// placed at the bounding box stored in the file. `density` scales the grid values and
+    /// `material` is the phase function
+    GridMedium {
+        path: PathBuf,
+        #[serde(default = "default_grid_density")]
+        density: f32,
+        material: String,
+        emission: Option<EmissionDescription>,
+    },
+}
+
+fn default_grid_density() -> f32 {
+    1.0
+}
+
+/// Radiance of the absorbing part of a grid medium, in the units of a diffuse light's `emit`
+#[derive(Deserialize, Clone)]
+#[serde(untagged, deny_unknown_fields)]
+pub enum EmissionDescription {
+    Colour([f32; 3]),
+    /// Black body colour of a temperature grid in kelvin with its spectrum peaking at 1, times
+    /// `intensity`. The coolest parts glow a dim red
+    Blackbody {
+        temperature: PathBuf,
+        #[serde(default = "default_emission_intensity")]
+        intensity: f32,
+    },
+}
+
+fn default_emission_intensity() -> f32 {
+    1.0
+}
+
+/// Closed shape enclosing a medium
+#[derive(Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum BoundaryDescription {
+    Sphere {
+        center: [f32; 3],
+        radius: f32,
+    },
+    /// Axis-aligned box spanning the corners `a` and `b`
+    Box {
+        a: [f32; 3],
+        b: [f32; 3],
+    },
+}
+
+impl BoundaryDescription {
+    /// The boundary is never shaded, `mat` only fills in the hit records it produces
+    fn build(&self, mat: Arc<dyn Material>) -> Result<Box<dyn Hittable>, String> {
+        match self {
+            Self::Sphere { radius, .. } if !is_positive(*radius) => {
+                Err("boundary sphere radius must be positive".to_string())
+            }
+            Self::Sphere { center, radius } => {
+                Ok(Box::new(Sphere::new(Point3::from(*center), *radius, mat)))
+            }
+            Self::Box { a, b } => Ok(Box::new(make_box(Point3::from(*a), Point3::from(*b), mat))),
+        }
+    }
+}
+
+/// Which list of the scene a built object belongs in
+#[derive(Clone, Copy)]
+pub(super) enum Role {
+    Surface,
+    /// Second copy of a glowing surface, sampled directly
+    Light,
+    Medium,
+}
+
+/// Hittables made from one object description, with the list each belongs in
+pub(super) type Built = Vec<(Role, Box<dyn Hittable>)>;
+
+/// Built objects gathered into the lists of a scene
+pub(super) struct SortedObjects {
+    pub(super) surfaces: HittableList,
+    pub(super) lights: HittableList,
+    pub(super) media: HittableList,
+}
+
+impl SortedObjects {
+    pub(super) fn new() -> Self {
+        Self {
+            surfaces: HittableList::new(),
+            lights: HittableList::new(),
+            media: HittableList::new(),
+        }
+    }
+
+    pub(super) fn add(&mut self, built: Built) {
+        for (role, hittable) in built {
+            match role {
+                Role::Surface => self.surfaces.add(hittable),
+                Role::Light => self.lights.add(hittable),
+                Role::Medium => self.media.add(hittable),
+            }
+        }
+    }
+}
+
+/// Shared parts of a `[geometry]` entry, one for each list of the scene it has objects in
+#[derive(Default)]
+pub(super) struct Geometry {
+    /// Bottom-level BVH over the surfaces
+    pub(super) surfaces: Option<Arc<dyn Hittable>>,
+    /// Kept as a plain list, which knows how to sample its members
+    pub(super) lights: Option<Arc<dyn Hittable>>,
+    pub(super) media: Option<Arc<dyn Hittable>>,
+}
+
+/// Turns object descriptions into hittables once the materials are built
+pub(super) struct ObjectBuilder<'a> {
+    pub(super) descriptions: &'a BTreeMap<String, Spanned<MaterialDescription>>,
+    pub(super) materials: &'a HashMap<&'a str, Arc<dyn Material>>,
+    pub(super) geometry: HashMap<&'a str, Geometry>,
+    pub(super) base_dir: &'a Path,
+}
+
+impl ObjectBuilder<'_> {
+    fn material(&self, name: &str) -> Result<Arc<dyn Material>, String> {
+        self.materials
+            .get(name)
+            .map(Arc::clone)
+            .ok_or_else(|| format!("unknown material '{}'", name))
+    }
+
+    fn phase_function(&self, name: &str) -> Result<Arc<dyn Material>, String> {
+        let material = self.material(name)?;
+        let is_phase_function = self.descriptions.get(name).is_some_and(|m| {
+            matches!(
+                m.get_ref(),
+                MaterialDescription::Isotropic { .. }
+                    | MaterialDescription::HenyeyGreenstein { .. }
+            )
+        });
+        if !is_phase_function {
+            return Err(format!(
+                "medium material '{}' must be isotropic or henyey_greenstein",
+                name
+            ));
+        }
+        Ok(material)
+    }
+
+    /// A shape made by `make`, with a second copy for the light list when it glows
+    fn sampled_surface(
+        &self,
+        material: &str,
+        make: impl Fn(Arc<dyn Material>) -> Box<dyn Hittable>,
+    ) -> Result<Built, String> {
+        let mat = self.material(material)?;
+        let mut built = vec![(Role::Surface, make(Arc::clone(&mat)))];
+        if self.is_light(material) {
+            built.push((Role::Light, make(mat)));
+        }
+        Ok(built)
+    }
+
+    /// Whether the material named `material` is a `diffuse_light`, whose surfaces are sampled
+    fn is_light(&self, material: &str) -> bool {
+        self.descriptions
+            .get(material)
+            .is_some_and(|m| matches!(m.get_ref(), MaterialDescription::DiffuseLight { .. }))
+    }
+
+    fn load_grid(&self, path: &Path) -> Result<VoxelGrid, String> {
+        let path = self.base_dir.join(path);
+        VoxelGrid::load(&path).map_err(|e| format!("cannot load {}: {}", path.display(), e))
+    }
+
+    /// One operand of a CSG object, lights among its surfaces still glow but are not sampled
+    fn solid(&self, object: &ObjectDescription) -> Result<Box<dyn Hittable>, String> {
+        let mut surfaces = Vec::new();
+        for (role, hittable) in self.build(object)? {
+            match role {
+                Role::Surface => surfaces.push(hittable),
+                Role::Light => {}
+                Role::Medium => return Err("csg operands cannot be media".to_string()),
+            }
+        }
+        if surfaces.len() == 1 {
+            return Ok(surfaces.remove(0));
+        }
+        let mut list = HittableList::new();
+        for surface in surfaces {
+            list.add(surface);
+        }
+        Ok(Box::new(list))
+    }
+
+    pub(super) fn build(&self, object: &ObjectDescription) -> Result<Built, String> {
+        match object {
+            ObjectDescription::Sphere { radius, .. } if !is_positive(*radius) => {
+                Err("sphere radius must be positive".to_string())
+            }
+            ObjectDescription::Sphere {
+                center,
+                center1: Some(center1),
+                radius,
+                material,
+            } => Ok(vec![(
+                Role::Surface,
+                Box::new(Sphere::moving(
+                    Point3::from(*center),
+                    Point3::from(*center1),
+                    *radius,
+                    self.material(material)?,
+                )),
+            )]),
+            ObjectDescription::Sphere {
+                center,
+                center1: None,
+                radius,
+                material,
+            } => self.sampled_surface(material, |mat| {
+                Box::new(Sphere::new(Point3::from(*center), *radius, mat))
+            }),
+            ObjectDescription::Quad { q, u, v, material } => {
+                if Vec3::from(*u).cross(&Vec3::from(*v)).near_zero() {
+                    return Err("quad edges u and v must not be parallel".to_string());
+                }
+                self.sampled_surface(material, |mat| {
+                    Box::new(Quad::new(
+                        Point3::from(*q),
+                        Vec3::from(*u),
+                        Vec3::from(*v),
+                        mat,
+                    ))
+                })
+            }
+            ObjectDescription::Triangle { a, b, c, material } => {
+                let (a, b, c) = (Point3::from(*a), Point3::from(*b), Point3::from(*c));
+                if (b - a).cross(&(c - a)).near_zero() {
+                    return Err("triangle vertices must not be collinear".to_string());
+                }
+                Ok(vec![(
+                    Role::Surface,
+                    Box::new(Triangle::new(a, b, c, self.material(material)?)),
+                )])
+            }
+            ObjectDescription::Box { a, b, material } => self.sampled_surface(material, |mat| {
+                Box::new(make_box(Point3::from(*a), Point3::from(*b), mat))
+            }),
+            ObjectDescription::Mesh { path, material } => {
+                let material = material
+                    .as_deref()
+                    .map(|name| self.material(name))
+                    .transpose()?;
+                let mesh = load_obj(&self.base_dir.join(path), material).map_err(|e| e.message)?;
+                Ok(vec![(Role::Surface, Box::new(mesh))])
+            }
+            ObjectDescription::ConstantMedium {
+                boundary,
+                density,
+                material,
+            } => {
+                if !is_positive(*density) {
+                    return Err("medium density must be positive".to_string());
+                }
+                let phase_function = self.phase_function(material)?;
+                Ok(vec![(
+                    Role::Medium,
+                    Box::new(ConstantMedium::new(
+                        boundary.build(Arc::clone(&phase_function))?,
+                        *density,
+                        phase_function,
+                    )),
+                )])
+            }
+            ObjectDescription::GridMedium {
+                path,
+                density,
+                material,
+                emission,
+            } => {
+                if !is_positive(*density) {
+                    return Err("medium density must be positive".to_string());
+                }
+                let phase_function = self.phase_function(material)?;
+                let emission = match emission {
+                    None => VolumeEmission::None,
+                    Some(EmissionDescription::Colour(colour)) => {
+                        VolumeEmission::Uniform(Colour::from(*colour))
+                    }
+                    Some(EmissionDescription::Blackbody {
+                        temperature,
+                        intensity,
+                    }) => VolumeEmission::Blackbody {
+                        temperature: self.load_grid(temperature)?,
+                        intensity: *intensity,
+                    },
+                };
+                Ok(vec![(
+                    Role::Medium,
+                    Box::new(GridMedium::new(
+                        self.load_grid(path)?,
+                        *density,
+                        phase_function,
+                        emission,
+                    )),
+                )])
+            }
+            ObjectDescription::Transform {
+                transform,
+                transform1: Some(transform1),
+                object,
+            } => {
+                // A moving light has no single position to sample, so only its surface stays
+                self.build(object)?
+                    .into_iter()
+                    .filter(|(role, _)| !matches!(role, Role::Light))
+                    .map(|(role, hittable)| {
+                        let animation = animate(transform, transform1)?;
+                        let moving = MovingTransform::new(Arc::from(hittable), animation);
+                        Ok((role, Box::new(moving) as Box<dyn Hittable>))
+                    })
+                    .collect()
+            }
+            ObjectDescription::Transform {
+                transform,
+                transform1: None,
+                object,
+            } => {
+                let to_world = compose(transform)?;
+                Ok(self
+                    .build(object)?
+                    .into_iter()
+                    .map(|(role, hittable)| {
+                        let transformed = Transform::new(Arc::from(hittable), to_world);
+                        (role, Box::new(transformed) as Box<dyn Hittable>)
+                    })
+                    .collect())
+            }
+            ObjectDescription::Sdf { shape, material } => Ok(vec![(
+                Role::Surface,
+                Box::new(Sdf::new(shape.build()?, self.material(material)?)),
+            )]),
+            ObjectDescription::Csg { operation, a, b } => Ok(vec![(
+                Role::Surface,
+                Box::new(Csg::new(*operation, self.solid(a)?, self.solid(b)?)),
+            )]),
+            ObjectDescription::Instance {
+                geometry,
+                transform,
+                material,
+            } => {
+                let parts = self
+                    .geometry
+                    .get(geometry.as_str())
+                    .ok_or_else(|| format!("unknown geometry '{}'", geometry))?;
+                let to_world = compose(transform)?;
+                // Lights of the geometry only stay lights when the override also emits
+                let lights = match material {
+                    Some(name) if !self.is_light(name) => &None,
+                    _ => &parts.lights,
+                };
+                let material = material
+                    .as_deref()
+                    .map(|name| self.material(name))
+                    .transpose()?;
+
+                let mut built: Built = Vec::new();
+                let roles = [
+                    (Role::Surface, &parts.surfaces, material.clone()),
+                    (Role::Light, lights, material),
+                    (Role::Medium, &parts.media, None),
+                ];
+                for (role, part, material) in roles {
+                    if let Some(part) = part {
+                        let instance = Instance::new(Arc::clone(part), to_world, material);
+                        built.push((role, Box::new(instance)));
+                    }
+                }
+                Ok(built)
+            }
+        }
+    }
+}
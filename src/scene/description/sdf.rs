@@ -0,0 +1,149 @@
+use serde::Deserialize;
+
+use crate::{hittable::sdf::SdfNode, Vec3};
+
+use super::is_positive;
+
+/// Node of a signed distance function tree, shapes sit around the origin until translated
+#[derive(Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum SdfDescription {
+    Sphere {
+        radius: f32,
+    },
+    /// Box with edges `size` long whose corners are rounded off by `radius`
+    RoundedBox {
+        size: [f32; 3],
+        #[serde(default)]
+        radius: f32,
+    },
+    /// Ring around the y axis
+    Torus {
+        major_radius: f32,
+        minor_radius: f32,
+    },
+    /// Fractal reaching about 1.2 from the origin, finer with more `iterations`
+    Mandelbulb {
+        #[serde(default = "default_mandelbulb_power")]
+        power: f32,
+        #[serde(default = "default_mandelbulb_iterations")]
+        iterations: usize,
+    },
+    Translate {
+        offset: [f32; 3],
+        shape: Box<SdfDescription>,
+    },
+    /// Union of `a` and `b` blended over about `k` units, 0 joins them with a sharp crease
+    SmoothUnion {
+        a: Box<SdfDescription>,
+        b: Box<SdfDescription>,
+        #[serde(default)]
+        k: f32,
+    },
+    /// Twist about the y axis by `angle` degrees per unit of height
+    Twist {
+        angle: f32,
+        shape: Box<SdfDescription>,
+    },
+    /// `count` copies along x, y and z, `spacing` apart and centred on the origin, each of
+    /// which must fit inside its cell
+    Repeat {
+        spacing: [f32; 3],
+        count: [usize; 3],
+        shape: Box<SdfDescription>,
+    },
+}
+
+fn default_mandelbulb_power() -> f32 {
+    8.0
+}
+
+fn default_mandelbulb_iterations() -> usize {
+    8
+}
+
+impl SdfDescription {
+    pub(super) fn build(&self) -> Result<SdfNode, String> {
+        let node = match self {
+            Self::Sphere { radius } => {
+                if !is_positive(*radius) {
+                    return Err("sdf sphere radius must be positive".to_string());
+                }
+                SdfNode::Sphere { radius: *radius }
+            }
+            Self::RoundedBox { size, radius } => {
+                if !size.iter().all(|&x| is_positive(x)) {
+                    return Err("sdf rounded_box size must be positive".to_string());
+                }
+                let smallest = size.iter().fold(f32::INFINITY, |a, &b| a.min(b));
+                if !(0.0..=smallest / 2.0).contains(radius) {
+                    return Err(
+                        "sdf rounded_box radius must lie between 0 and half its smallest edge"
+                            .to_string(),
+                    );
+                }
+                SdfNode::RoundedBox {
+                    half_size: Vec3::from(*size).scale(0.5) - Vec3::new(*radius, *radius, *radius),
+                    radius: *radius,
+                }
+            }
+            Self::Torus {
+                major_radius,
+                minor_radius,
+            } => {
+                if !is_positive(*major_radius) || !is_positive(*minor_radius) {
+                    return Err("sdf torus radii must be positive".to_string());
+                }
+                SdfNode::Torus {
+                    major_radius: *major_radius,
+                    minor_radius: *minor_radius,
+                }
+            }
+            Self::Mandelbulb { power, iterations } => {
+                if !(*power >= 2.0 && power.is_finite()) {
+                    return Err("mandelbulb power must be at least 2".to_string());
+                }
+                SdfNode::Mandelbulb {
+                    power: *power,
+                    iterations: *iterations,
+                }
+            }
+            Self::Translate { offset, shape } => SdfNode::Translate {
+                offset: Vec3::from(*offset),
+                shape: Box::new(shape.build()?),
+            },
+            Self::SmoothUnion { a, b, k } => {
+                if !(*k >= 0.0 && k.is_finite()) {
+                    return Err("sdf smooth_union k cannot be negative".to_string());
+                }
+                SdfNode::SmoothUnion {
+                    a: Box::new(a.build()?),
+                    b: Box::new(b.build()?),
+                    k: *k,
+                }
+            }
+            Self::Twist { angle, shape } => SdfNode::Twist {
+                rate: angle.to_radians(),
+                shape: Box::new(shape.build()?),
+            },
+            Self::Repeat {
+                spacing,
+                count,
+                shape,
+            } => {
+                if !spacing.iter().all(|&x| is_positive(x)) {
+                    return Err("sdf repeat spacing must be positive".to_string());
+                }
+                if count.contains(&0) {
+                    return Err("sdf repeat count must be at least 1".to_string());
+                }
+                SdfNode::Repeat {
+                    spacing: Vec3::from(*spacing),
+                    count: *count,
+                    shape: Box::new(shape.build()?),
+                }
+            }
+        };
+        Ok(node)
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::Deserialize;
+use toml::Spanned;
+
+use crate::{
+    hittable::texture::{
+        checker::CheckerTexture,
+        image::{Filter, ImageData, ImageTexture, WrapMode},
+        noise::{NoisePattern, NoiseTexture, Perlin},
+        solid_colour::SolidColour,
+        Texture,
+    },
+    scene::SceneError,
+    utils::sampler::Sampler,
+    Colour,
+};
+
+use super::{is_positive, line_of};
+
+/// Either an inline colour or the name of an entry in `[textures]`
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+pub enum TextureRef {
+    Colour([f32; 3]),
+    Named(String),
+}
+
+/// Either an inline number or the name of an entry in `[textures]`, whose channels are averaged
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+pub enum ScalarRef {
+    Value(f32),
+    Named(String),
+}
+
+fn default_noise_scale() -> f32 {
+    1.0
+}
+
+fn default_noise_depth() -> usize {
+    7
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum TextureDescription {
+    Solid {
+        colour: [f32; 3],
+    },
+    /// 3D checker of cubes with edge length `scale`
+    Checker {
+        scale: f32,
+        even: TextureRef,
+        odd: TextureRef,
+    },
+    /// PNG, PPM, HDR or EXR file relative to the scene file
+    Image {
+        path: PathBuf,
+        #[serde(default = "default_wrap")]
+        wrap: WrapMode,
+        #[serde(default = "default_filter")]
+        filter: Filter,
+    },
+    /// Perlin noise, `depth` is the number of turbulence octaves
+    Noise {
+        #[serde(default = "default_noise_scale")]
+        scale: f32,
+        #[serde(default = "default_noise_pattern")]
+        pattern: NoisePattern,
+        #[serde(default = "default_noise_depth")]
+        depth: usize,
+    },
+}
+
+fn default_wrap() -> WrapMode {
+    WrapMode::Repeat
+}
+
+fn default_filter() -> Filter {
+    Filter::Bilinear
+}
+
+fn default_noise_pattern() -> NoisePattern {
+    NoisePattern::Noise
+}
+
+/// Builds named textures on first use, so textures can refer to each other in any order
+pub(super) struct TextureResolver<'a> {
+    pub(super) descriptions: &'a BTreeMap<String, Spanned<TextureDescription>>,
+    pub(super) built: HashMap<&'a str, Arc<dyn Texture>>,
+    pub(super) in_progress: HashSet<&'a str>,
+    pub(super) source: &'a str,
+    pub(super) base_dir: &'a Path,
+    pub(super) seed: u64,
+}
+
+impl<'a> TextureResolver<'a> {
+    /// Inline values must lie in [0, 1]
+    pub(super) fn resolve_scalar(
+        &mut self,
+        scalar: &ScalarRef,
+    ) -> Result<Arc<dyn Texture>, SceneError> {
+        match scalar {
+            ScalarRef::Value(x) => {
+                if !(0.0..=1.0).contains(x) {
+                    return Err(SceneError {
+                        line: None,
+                        message: format!("{} is outside [0, 1]", x),
+                    });
+                }
+                Ok(Arc::new(SolidColour::new(Colour::new(*x, *x, *x))))
+            }
+            ScalarRef::Named(name) => self.resolve(&TextureRef::Named(name.clone())),
+        }
+    }
+
+    /// Errors without a line come from the reference itself rather than a texture definition
+    pub(super) fn resolve(&mut self, texture: &TextureRef) -> Result<Arc<dyn Texture>, SceneError> {
+        let name = match texture {
+            TextureRef::Colour(colour) => {
+                return Ok(Arc::new(SolidColour::new(Colour::from(*colour))))
+            }
+            TextureRef::Named(name) => name,
+        };
+
+        let (key, description) =
+            self.descriptions
+                .get_key_value(name)
+                .ok_or_else(|| SceneError {
+                    line: None,
+                    message: format!("unknown texture '{}'", name),
+                })?;
+        if let Some(texture) = self.built.get(key.as_str()) {
+            return Ok(Arc::clone(texture));
+        }
+
+        let error_here = |message: String| SceneError {
+            line: line_of(self.source, description.span()),
+            message: format!("{}: {}", key, message),
+        };
+        if !self.in_progress.insert(key.as_str()) {
+            return Err(error_here("texture refers to itself".to_string()));
+        }
+
+        let texture: Arc<dyn Texture> = match description.get_ref() {
+            TextureDescription::Solid { colour } => {
+                Arc::new(SolidColour::new(Colour::from(*colour)))
+            }
+            TextureDescription::Checker { scale, even, odd } => {
+                if !is_positive(*scale) {
+                    return Err(error_here("checker scale must be positive".to_string()));
+                }
+                let line = line_of(self.source, description.span());
+                let mut resolve_here = |texture| {
+                    self.resolve(texture).map_err(|e| match e.line {
+                        Some(_) => e,
+                        None => SceneError {
+                            line,
+                            message: format!("{}: {}", key, e.message),
+                        },
+                    })
+                };
+                let even = resolve_here(even)?;
+                let odd = resolve_here(odd)?;
+                Arc::new(CheckerTexture::new(*scale, even, odd))
+            }
+            TextureDescription::Image { path, wrap, filter } => {
+                let path = self.base_dir.join(path);
+                let image = ImageData::load(&path)
+                    .map_err(|e| error_here(format!("cannot load {}: {}", path.display(), e)))?;
+                Arc::new(ImageTexture::new(image, *wrap, *filter))
+            }
+            TextureDescription::Noise {
+                scale,
+                pattern,
+                depth,
+            } => {
+                // Every noise texture gets its own lattice, stable across edits to other textures
+                let stream = key.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+                    (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+                });
+                let perlin = Perlin::new(&mut Sampler::new(self.seed, stream));
+                Arc::new(NoiseTexture::new(perlin, *scale, *pattern, *depth))
+            }
+        };
+
+        self.in_progress.remove(key.as_str());
+        self.built.insert(key.as_str(), Arc::clone(&texture));
+        Ok(texture)
+    }
+}
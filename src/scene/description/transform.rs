@@ -0,0 +1,173 @@
+use serde::Deserialize;
+
+use crate::{hittable::transform::Animation, utils::matrix::Mat4, Vec3};
+
+/// One step of an object transform
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum TransformStep {
+    Translate([f32; 3]),
+    /// Counter-clockwise by `angle` degrees looking down `axis` towards the origin
+    Rotate {
+        axis: [f32; 3],
+        angle: f32,
+    },
+    /// Factors along x, y and z
+    Scale([f32; 3]),
+    /// Rows of an affine matrix, the last one must be [0, 0, 0, 1]
+    Matrix([[f32; 4]; 4]),
+}
+
+/// Matrix applying `steps` in order
+pub(super) fn compose(steps: &[TransformStep]) -> Result<Mat4, String> {
+    let mut to_world = Mat4::IDENTITY;
+    for step in steps {
+        to_world = step.matrix()? * to_world;
+    }
+    if to_world.inverse().is_none() {
+        return Err("transform is not invertible".to_string());
+    }
+    Ok(to_world)
+}
+
+/// Matrices of the steps between `start` at time 0 and `end` at time 1, checked to stay
+/// invertible along the way
+pub(super) fn animate(start: &[TransformStep], end: &[TransformStep]) -> Result<Animation, String> {
+    if start.len() != end.len() {
+        return Err("transform1 must have as many steps as transform".to_string());
+    }
+    // Both ends are valid and every step keeps a nonzero determinant in between, so their
+    // product stays invertible
+    compose(start)?;
+    compose(end)?;
+    let steps = start
+        .iter()
+        .zip(end)
+        .map(|(a, b)| a.animate(b))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Box::new(move |time| {
+        steps
+            .iter()
+            .fold(Mat4::IDENTITY, |to_world, step| step(time) * to_world)
+    }))
+}
+
+impl TransformStep {
+    /// Matrix of this step moved linearly towards `other` over time, which must be the same
+    /// kind of step and keep the matrix invertible all the way there
+    fn animate(&self, other: &Self) -> Result<Animation, String> {
+        fn mix(a: f32, b: f32, t: f32) -> f32 {
+            a + t * (b - a)
+        }
+        fn mix3(a: [f32; 3], b: [f32; 3], t: f32) -> Vec3 {
+            Vec3::from(std::array::from_fn(|i| mix(a[i], b[i], t)))
+        }
+        match (*self, *other) {
+            (Self::Translate(a), Self::Translate(b)) => {
+                Ok(Box::new(move |t| Mat4::translate(&mix3(a, b, t))))
+            }
+            (
+                Self::Rotate { axis, angle },
+                Self::Rotate {
+                    axis: axis1,
+                    angle: angle1,
+                },
+            ) => {
+                // The point of the path between the axes closest to zero
+                let (a, d) = (Vec3::from(axis), Vec3::from(axis1) - Vec3::from(axis));
+                let closest = if d.near_zero() {
+                    0.0
+                } else {
+                    (-a.dot(&d) / d.dot(&d)).clamp(0.0, 1.0)
+                };
+                if (a + d.scale(closest)).near_zero() {
+                    return Err(
+                        "rotation axis must not pass through zero on its way to transform1"
+                            .to_string(),
+                    );
+                }
+                Ok(Box::new(move |t| {
+                    Mat4::rotate(&mix3(axis, axis1, t), mix(angle, angle1, t))
+                }))
+            }
+            (Self::Scale(a), Self::Scale(b)) => {
+                if a.iter().zip(&b).any(|(a, b)| a * b <= 0.0) {
+                    return Err("scale factors must keep their sign in transform1".to_string());
+                }
+                Ok(Box::new(move |t| Mat4::scale(&mix3(a, b, t))))
+            }
+            (Self::Matrix(a), Self::Matrix(b)) => {
+                let at = move |t: f32| {
+                    Mat4::new(std::array::from_fn(|i| {
+                        std::array::from_fn(|j| mix(a[i][j], b[i][j], t))
+                    }))
+                };
+                if !keeps_sign(|t| at(t).linear_determinant()) {
+                    return Err(
+                        "matrix determinant must keep its sign on its way to transform1"
+                            .to_string(),
+                    );
+                }
+                Ok(Box::new(at))
+            }
+            _ => Err("steps of transform1 must match the kinds of steps in transform".to_string()),
+        }
+    }
+
+    fn matrix(&self) -> Result<Mat4, String> {
+        match self {
+            Self::Translate(offset) => Ok(Mat4::translate(&Vec3::from(*offset))),
+            Self::Rotate { axis, angle } => {
+                let axis = Vec3::from(*axis);
+                if axis.near_zero() {
+                    return Err("rotation axis must not be zero".to_string());
+                }
+                Ok(Mat4::rotate(&axis, *angle))
+            }
+            Self::Scale(factors) => {
+                if factors.contains(&0.0) {
+                    return Err("scale factors must not be zero".to_string());
+                }
+                Ok(Mat4::scale(&Vec3::from(*factors)))
+            }
+            Self::Matrix(rows) => {
+                let matrix = Mat4::new(*rows);
+                if !matrix.is_affine() {
+                    return Err(
+                        "the last row of a transform matrix must be [0, 0, 0, 1]".to_string()
+                    );
+                }
+                Ok(matrix)
+            }
+        }
+    }
+}
+
+/// Whether the cubic `p` stays clear of zero over [0, 1], as the determinant of a matrix
+/// blended linearly between two others does. A cubic can only cross zero where it changes
+/// sign between its ends and turning points
+fn keeps_sign(p: impl Fn(f32) -> f32) -> bool {
+    // Coefficients in s = 3t from the forward differences of samples a third apart
+    let [p0, p1, p2, p3] = [0.0, 1.0, 2.0, 3.0].map(|s: f32| f64::from(p(s / 3.0)));
+    let (d1, d2, d3) = (p1 - p0, p2 - 2.0 * p1 + p0, p3 - 3.0 * p2 + 3.0 * p1 - p0);
+    let (c1, c2, c3) = (d1 - d2 / 2.0 + d3 / 3.0, (d2 - d3) / 2.0, d3 / 6.0);
+    let cubic = |s: f64| p0 + s * (c1 + s * (c2 + s * c3));
+
+    // Roots of the derivative 3 c3 s^2 + 2 c2 s + c1
+    let mut points = vec![0.0, 3.0];
+    if c3 != 0.0 {
+        let discriminant = c2 * c2 - 3.0 * c3 * c1;
+        if discriminant >= 0.0 {
+            let root = discriminant.sqrt();
+            points.extend([(-c2 - root) / (3.0 * c3), (-c2 + root) / (3.0 * c3)]);
+        }
+    } else if c2 != 0.0 {
+        points.push(-c1 / (2.0 * c2));
+    }
+    // Samples only carry f32 precision, so dips that close to zero count as reaching it
+    let margin = 1e-6 * p0.abs().max(p3.abs());
+    points
+        .into_iter()
+        .filter(|s| (0.0..=3.0).contains(s))
+        .all(|s| cubic(s) * p0.signum() > margin)
+}
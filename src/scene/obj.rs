@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::{
+    hittable::{
+        material::{
+            dielectric::Dielectric, diffuse_light::DiffuseLight, lambertian::Lambertian,
+            metal::Metal, Material,
+        },
+        mesh::{Mesh, MeshData, MeshFace, MeshVertex},
+        texture::image::{Filter, ImageData, ImageTexture, WrapMode},
+    },
+    Colour, Point3, Vec3,
+};
+
+use super::SceneError;
+
+/// Subset of an MTL material that maps onto the built-in materials
+struct MtlMaterial {
+    diffuse: Colour,
+    /// `map_Kd`, already resolved against the MTL file's directory and tinted by `diffuse`
+    diffuse_map: Option<PathBuf>,
+    specular: Colour,
+    emission: Colour,
+    shininess: f32,
+    ior: f32,
+    dissolve: f32,
+    illum: u32,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> Self {
+        Self {
+            diffuse: Colour::new(0.8, 0.8, 0.8),
+            diffuse_map: None,
+            specular: Colour::default(),
+            emission: Colour::default(),
+            shininess: 0.0,
+            ior: 1.5,
+            dissolve: 1.0,
+            illum: 2,
+        }
+    }
+}
+
+impl MtlMaterial {
+    fn build(&self) -> Result<Arc<dyn Material>, SceneError> {
+        let max = |c: Colour| c.x().max(c.y()).max(c.z());
+
+        if max(self.emission) > 0.0 {
+            return Ok(Arc::new(DiffuseLight::new(self.emission)));
+        }
+
+        // Illumination models 4, 6, 7 and 9 are the refractive ones
+        if matches!(self.illum, 4 | 6 | 7 | 9) || self.dissolve < 1.0 {
+            return Ok(Arc::new(Dielectric::new(self.ior)));
+        }
+
+        if matches!(self.illum, 3 | 5 | 8) || max(self.specular) > max(self.diffuse) {
+            // Rough Phong lobes become fuzzy reflections
+            let fuzz = (2.0 / (self.shininess + 2.0)).sqrt();
+            return Ok(Arc::new(Metal::new(self.specular, fuzz)));
+        }
+
+        if let Some(path) = &self.diffuse_map {
+            let mut image = ImageData::load(path).map_err(|e| SceneError {
+                line: None,
+                message: format!("cannot load {}: {}", path.display(), e),
+            })?;
+            for texel in &mut image.texels {
+                *texel = *texel * self.diffuse;
+            }
+            let texture = ImageTexture::new(image, WrapMode::Repeat, Filter::Bilinear);
+            return Ok(Arc::new(Lambertian::from_texture(Arc::new(texture))));
+        }
+
+        Ok(Arc::new(Lambertian::new(self.diffuse)))
+    }
+}
+
+fn error(path: &Path, line: usize, message: impl Into<String>) -> SceneError {
+    SceneError {
+        line: None,
+        message: format!("{} line {}: {}", path.display(), line, message.into()),
+    }
+}
+
+fn read(path: &Path) -> Result<String, SceneError> {
+    fs::read_to_string(path).map_err(|e| SceneError {
+        line: None,
+        message: format!("cannot read {}: {}", path.display(), e),
+    })
+}
+
+fn parse_floats<const N: usize>(
+    args: &[&str],
+    path: &Path,
+    line: usize,
+) -> Result<[f32; N], SceneError> {
+    if args.len() < N {
+        return Err(error(path, line, format!("expected {} numbers", N)));
+    }
+
+    let mut values = [0.0; N];
+    for (value, arg) in values.iter_mut().zip(args) {
+        *value = arg
+            .parse()
+            .map_err(|_| error(path, line, format!("invalid number '{}'", arg)))?;
+    }
+    Ok(values)
+}
+
+fn load_mtl(path: &Path, materials: &mut HashMap<String, MtlMaterial>) -> Result<(), SceneError> {
+    let source = read(path)?;
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    let mut current: Option<String> = None;
+
+    for (n, raw) in source.lines().enumerate() {
+        let line = n + 1;
+        let mut words = raw.split_whitespace();
+        let Some(keyword) = words.next() else {
+            continue;
+        };
+        let args: Vec<&str> = words.collect();
+
+        if keyword == "newmtl" {
+            let name = args.join(" ");
+            materials.insert(name.clone(), MtlMaterial::default());
+            current = Some(name);
+            continue;
+        }
+
+        let Some(material) = current.as_ref().and_then(|name| materials.get_mut(name)) else {
+            if keyword.starts_with('#') {
+                continue;
+            }
+            return Err(error(
+                path,
+                line,
+                format!("'{}' before any newmtl", keyword),
+            ));
+        };
+
+        match keyword {
+            "Kd" => material.diffuse = Colour::from(parse_floats::<3>(&args, path, line)?),
+            "Ks" => material.specular = Colour::from(parse_floats::<3>(&args, path, line)?),
+            "Ke" => material.emission = Colour::from(parse_floats::<3>(&args, path, line)?),
+            "Ns" => material.shininess = parse_floats::<1>(&args, path, line)?[0],
+            "Ni" => material.ior = parse_floats::<1>(&args, path, line)?[0],
+            "d" => material.dissolve = parse_floats::<1>(&args, path, line)?[0],
+            "Tr" => material.dissolve = 1.0 - parse_floats::<1>(&args, path, line)?[0],
+            // Map options such as -clamp or -s come first, the file name is last
+            "map_Kd" => {
+                let file = args
+                    .last()
+                    .ok_or_else(|| error(path, line, "expected a texture file"))?;
+                material.diffuse_map = Some(base_dir.join(file));
+            }
+            "illum" => {
+                material.illum = args
+                    .first()
+                    .and_then(|arg| arg.parse().ok())
+                    .ok_or_else(|| error(path, line, "expected an illumination model"))?
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+// Resolves a 1-based, possibly negative OBJ index against the number of elements read so far
+fn resolve_index(index: &str, count: usize, path: &Path, line: usize) -> Result<usize, SceneError> {
+    let i: i64 = index
+        .parse()
+        .map_err(|_| error(path, line, format!("invalid index '{}'", index)))?;
+
+    let resolved = if i < 0 { count as i64 + i } else { i - 1 };
+    if i == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(error(path, line, format!("index {} is out of range", i)));
+    }
+    Ok(resolved as usize)
+}
+
+/// Loads a Wavefront OBJ file and the MTL libraries it references
+///
+/// Faces without a material are grey and diffuse. When `override_material` is set it replaces
+/// every material from the file.
+pub fn load_obj(
+    path: &Path,
+    override_material: Option<Arc<dyn Material>>,
+) -> Result<Mesh, SceneError> {
+    let source = read(path)?;
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+
+    let mut data = MeshData::default();
+    data.materials
+        .push(Arc::new(Lambertian::new(Colour::new(0.8, 0.8, 0.8))));
+
+    let mut mtl_materials = HashMap::new();
+    let mut material_indices: HashMap<String, usize> = HashMap::new();
+    let mut current_material = 0;
+
+    for (n, raw) in source.lines().enumerate() {
+        let line = n + 1;
+        let mut words = raw.split_whitespace();
+        let Some(keyword) = words.next() else {
+            continue;
+        };
+        let args: Vec<&str> = words.collect();
+
+        match keyword {
+            "v" => data
+                .positions
+                .push(Point3::from(parse_floats::<3>(&args, path, line)?)),
+            "vn" => data
+                .normals
+                .push(Vec3::from(parse_floats::<3>(&args, path, line)?)),
+            "vt" => {
+                // The optional w coordinate is ignored
+                let uv = parse_floats::<1>(&args, path, line)?;
+                let v = match args.get(1) {
+                    Some(_) => parse_floats::<2>(&args, path, line)?[1],
+                    None => 0.0,
+                };
+                data.texcoords.push([uv[0], v]);
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err(error(path, line, "a face needs at least three vertices"));
+                }
+
+                let mut vertices = Vec::with_capacity(args.len());
+                for arg in &args {
+                    let mut parts = arg.split('/');
+                    let position = resolve_index(
+                        parts.next().unwrap_or(""),
+                        data.positions.len(),
+                        path,
+                        line,
+                    )?;
+                    let texcoord = match parts.next() {
+                        Some(t) if !t.is_empty() => {
+                            Some(resolve_index(t, data.texcoords.len(), path, line)?)
+                        }
+                        _ => None,
+                    };
+                    let normal = match parts.next() {
+                        Some(n) if !n.is_empty() => {
+                            Some(resolve_index(n, data.normals.len(), path, line)?)
+                        }
+                        _ => None,
+                    };
+                    vertices.push(MeshVertex {
+                        position,
+                        normal,
+                        texcoord,
+                    });
+                }
+
+                // Polygons are split into a fan around their first vertex
+                for k in 1..vertices.len() - 1 {
+                    data.faces.push(MeshFace {
+                        vertices: [vertices[0], vertices[k], vertices[k + 1]],
+                        material: current_material,
+                    });
+                }
+            }
+            "mtllib" => {
+                for library in &args {
+                    load_mtl(&base_dir.join(library), &mut mtl_materials)?;
+                }
+            }
+            "usemtl" => {
+                let name = args.join(" ");
+                let mtl = mtl_materials
+                    .get(&name)
+                    .ok_or_else(|| error(path, line, format!("unknown material '{}'", name)))?;
+
+                current_material = match material_indices.get(&name) {
+                    Some(&index) => index,
+                    None => {
+                        data.materials.push(mtl.build()?);
+                        material_indices.insert(name, data.materials.len() - 1);
+                        data.materials.len() - 1
+                    }
+                };
+            }
+            // Groups, objects, smoothing groups and comments do not affect rendering
+            _ => {}
+        }
+    }
+
+    if data.faces.is_empty() {
+        return Err(SceneError {
+            line: None,
+            message: format!("{} has no faces", path.display()),
+        });
+    }
+
+    if let Some(material) = override_material {
+        data.materials = vec![material];
+        for face in &mut data.faces {
+            face.material = 0;
+        }
+    }
+
+    Ok(Mesh::new(data))
+}
@@ -0,0 +1,232 @@
+use toml::Spanned;
+
+use crate::{utils::sampler::Sampler, Point3, Vec3};
+
+use super::description::{
+    background::BackgroundDescription,
+    material::{IorDescription, MaterialDescription},
+    object::ObjectDescription,
+    texture::TextureRef,
+    CameraDescription, SceneDescription,
+};
+
+/// Names accepted by `Scene::preset`
+pub const NAMES: [&str; 2] = ["final", "cornell"];
+
+pub fn description(name: &str, seed: u64) -> Option<SceneDescription> {
+    match name {
+        "final" => Some(final_scene(seed)),
+        "cornell" => Some(cornell_box(seed)),
+        _ => None,
+    }
+}
+
+fn spanned<T>(value: T) -> Spanned<T> {
+    Spanned::new(0..0, value)
+}
+
+fn add_sphere(
+    scene: &mut SceneDescription,
+    center: Point3,
+    radius: f32,
+    name: String,
+    mat: MaterialDescription,
+) {
+    scene.objects.push(spanned(ObjectDescription::Sphere {
+        center: [center.x(), center.y(), center.z()],
+        center1: None,
+        radius,
+        material: name.clone(),
+    }));
+    scene.materials.insert(name, spanned(mat));
+}
+
+/// Cover image of "Ray Tracing in One Weekend", small spheres are scattered using `seed`
+fn final_scene(seed: u64) -> SceneDescription {
+    let mut sampler = Sampler::new(seed, u64::MAX);
+    let mut scene = SceneDescription {
+        camera: spanned(CameraDescription {
+            vfov: 20.0,
+            lookfrom: [13.0, 2.0, 3.0],
+            lookat: [0.0, 0.0, 0.0],
+            defocus_angle: 0.6,
+            focus_dist: 10.0,
+            seed,
+            ..CameraDescription::default()
+        }),
+        ..SceneDescription::default()
+    };
+
+    add_sphere(
+        &mut scene,
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        "ground".to_string(),
+        MaterialDescription::Lambertian {
+            albedo: TextureRef::Colour([0.5, 0.5, 0.5]),
+        },
+    );
+
+    for a in -11..11 {
+        for b in -11..11 {
+            let choose_mat = sampler.random_float();
+            let center = Point3::new(
+                a as f32 + 0.9 * sampler.random_float(),
+                0.2,
+                b as f32 + 0.9 * sampler.random_float(),
+            );
+
+            if (center - Point3::new(4.0, 0.2, 0.0)).length() > 0.9 {
+                let mat = if choose_mat < 0.8 {
+                    // Diffuse
+                    let albedo = Vec3::random(&mut sampler) * Vec3::random(&mut sampler);
+                    MaterialDescription::Lambertian {
+                        albedo: TextureRef::Colour([albedo.x(), albedo.y(), albedo.z()]),
+                    }
+                } else if choose_mat < 0.95 {
+                    // Metal
+                    let albedo = Vec3::random_range(&mut sampler, 0.5, 1.0);
+                    let fuzz = sampler.random_float_range(0.0, 0.5);
+                    MaterialDescription::Metal {
+                        albedo: TextureRef::Colour([albedo.x(), albedo.y(), albedo.z()]),
+                        fuzz,
+                        coating: None,
+                    }
+                } else {
+                    // Glass
+                    MaterialDescription::Dielectric {
+                        ir: IorDescription::Constant(1.5),
+                        coating: None,
+                    }
+                };
+
+                add_sphere(&mut scene, center, 0.2, format!("sphere_{}_{}", a, b), mat);
+            }
+        }
+    }
+
+    add_sphere(
+        &mut scene,
+        Point3::new(0.0, 1.0, 0.0),
+        1.0,
+        "material1".to_string(),
+        MaterialDescription::Dielectric {
+            ir: IorDescription::Constant(1.5),
+            coating: None,
+        },
+    );
+    add_sphere(
+        &mut scene,
+        Point3::new(-4.0, 1.0, 0.0),
+        1.0,
+        "material2".to_string(),
+        MaterialDescription::Lambertian {
+            albedo: TextureRef::Colour([0.4, 0.2, 0.1]),
+        },
+    );
+    add_sphere(
+        &mut scene,
+        Point3::new(4.0, 1.0, 0.0),
+        1.0,
+        "material3".to_string(),
+        MaterialDescription::Metal {
+            albedo: TextureRef::Colour([0.7, 0.6, 0.5]),
+            fuzz: 0.0,
+            coating: None,
+        },
+    );
+
+    scene
+}
+
+/// Cornell box with two white blocks, lit only by the ceiling light
+fn cornell_box(seed: u64) -> SceneDescription {
+    let mut scene = SceneDescription {
+        camera: spanned(CameraDescription {
+            aspect_ratio: 1.0,
+            image_width: 600,
+            samples_per_pixel: 200,
+            vfov: 40.0,
+            lookfrom: [278.0, 278.0, -800.0],
+            lookat: [278.0, 278.0, 0.0],
+            focus_dist: 10.0,
+            background: BackgroundDescription::None,
+            seed,
+            ..CameraDescription::default()
+        }),
+        ..SceneDescription::default()
+    };
+
+    let lambertian = |albedo| {
+        spanned(MaterialDescription::Lambertian {
+            albedo: TextureRef::Colour(albedo),
+        })
+    };
+    let materials = &mut scene.materials;
+    materials.insert("red".to_string(), lambertian([0.65, 0.05, 0.05]));
+    materials.insert("white".to_string(), lambertian([0.73, 0.73, 0.73]));
+    materials.insert("green".to_string(), lambertian([0.12, 0.45, 0.15]));
+    materials.insert(
+        "light".to_string(),
+        spanned(MaterialDescription::DiffuseLight {
+            emit: [15.0, 15.0, 15.0],
+        }),
+    );
+
+    // Corner, edges and material of the walls and the ceiling light
+    let quads = [
+        (
+            [555.0, 0.0, 0.0],
+            [0.0, 555.0, 0.0],
+            [0.0, 0.0, 555.0],
+            "green",
+        ),
+        ([0.0, 0.0, 0.0], [0.0, 555.0, 0.0], [0.0, 0.0, 555.0], "red"),
+        (
+            [343.0, 554.0, 332.0],
+            [-130.0, 0.0, 0.0],
+            [0.0, 0.0, -105.0],
+            "light",
+        ),
+        (
+            [0.0, 0.0, 0.0],
+            [555.0, 0.0, 0.0],
+            [0.0, 0.0, 555.0],
+            "white",
+        ),
+        (
+            [555.0, 555.0, 555.0],
+            [-555.0, 0.0, 0.0],
+            [0.0, 0.0, -555.0],
+            "white",
+        ),
+        (
+            [0.0, 0.0, 555.0],
+            [555.0, 0.0, 0.0],
+            [0.0, 555.0, 0.0],
+            "white",
+        ),
+    ];
+    for (q, u, v, material) in quads {
+        scene.objects.push(spanned(ObjectDescription::Quad {
+            q,
+            u,
+            v,
+            material: material.to_string(),
+        }));
+    }
+
+    let blocks = [
+        ([130.0, 0.0, 65.0], [295.0, 165.0, 230.0]),
+        ([265.0, 0.0, 295.0], [430.0, 330.0, 460.0]),
+    ];
+    for (a, b) in blocks {
+        scene.objects.push(spanned(ObjectDescription::Box {
+            a,
+            b,
+            material: "white".to_string(),
+        }));
+    }
+
+    scene
+}
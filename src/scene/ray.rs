@@ -1,14 +1,29 @@
 use crate::{Point3, Vec3};
 
-#[derive(Default)]
+#[derive(Clone, Copy, Default)]
 pub struct Ray {
     origin: Point3,
     direction: Vec3,
+    time: f32,
+    /// Hero wavelength in nanometres of a spectral path, None when rendering in RGB
+    wavelength: Option<f32>,
 }
 
 impl Ray {
-    pub fn new(origin: Point3, direction: Vec3) -> Self {
-        Ray { origin, direction }
+    /// `time` is the moment within the camera shutter the ray exists at, which moving objects
+    /// use to decide where they are
+    pub fn new(origin: Point3, direction: Vec3, time: f32) -> Self {
+        Ray {
+            origin,
+            direction,
+            time,
+            wavelength: None,
+        }
+    }
+
+    /// The same ray carrying the hero `wavelength` of the path it belongs to
+    pub fn with_wavelength(self, wavelength: Option<f32>) -> Self {
+        Ray { wavelength, ..self }
     }
 
     pub fn origin(&self) -> &Point3 {
@@ -19,6 +34,14 @@ impl Ray {
         &self.direction
     }
 
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn wavelength(&self) -> Option<f32> {
+        self.wavelength
+    }
+
     pub fn at(&self, t: f32) -> Point3 {
         self.origin + self.direction.scale(t)
     }
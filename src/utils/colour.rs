@@ -1,6 +1,3 @@
-use std::fs::File;
-use std::io::Write;
-
 use crate::Vec3;
 
 use super::interval::Interval;
@@ -10,17 +7,15 @@ pub type Colour = Vec3;
 fn linear_to_gamma(linear_component: f32) -> f32 {
     linear_component.sqrt()
 }
-pub fn write_colour(file: &mut File, pixel_colour: Colour, samples_per_pixel: usize) {
-    let scaled_pixel_colour = pixel_colour.scale(1.0 / samples_per_pixel as f32);
-    let r = linear_to_gamma(scaled_pixel_colour.x());
-    let g = linear_to_gamma(scaled_pixel_colour.y());
-    let b = linear_to_gamma(scaled_pixel_colour.z());
 
+/// Gamma encodes a linear colour and quantises it to 8 bits per channel
+pub fn to_rgb8(pixel_colour: Colour) -> [u8; 3] {
     let intensity = Interval::new(0.000, 0.999);
+    let quantise = |c: f32| (256.0 * intensity.clamp(linear_to_gamma(c))) as u8;
 
-    let ir = (256.0 * intensity.clamp(r)) as i32;
-    let ig = (256.0 * intensity.clamp(g)) as i32;
-    let ib = (256.0 * intensity.clamp(b)) as i32;
-    file.write_all(format!("{} {} {}\n", ir, ig, ib).as_bytes())
-        .unwrap();
+    [
+        quantise(pixel_colour.x()),
+        quantise(pixel_colour.y()),
+        quantise(pixel_colour.z()),
+    ]
 }
@@ -0,0 +1,28 @@
+use super::colour::{to_rgb8, Colour};
+
+/// Linear radiance for every pixel of a finished render, stored row by row from the top
+pub struct Framebuffer {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Colour>,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![Colour::default(); width * height],
+        }
+    }
+
+    pub fn row_mut(&mut self, j: usize) -> &mut [Colour] {
+        let start = j * self.width;
+        &mut self.pixels[start..start + self.width]
+    }
+
+    /// Gamma encoded 8-bit RGB triples in the same order as `pixels`
+    pub fn to_rgb8(&self) -> Vec<u8> {
+        self.pixels.iter().flat_map(|&c| to_rgb8(c)).collect()
+    }
+}
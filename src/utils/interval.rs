@@ -1,13 +1,36 @@
+#[derive(Clone, Copy, Debug)]
 pub struct Interval {
     pub min: f32,
     pub max: f32,
 }
 
 impl Interval {
+    pub const EMPTY: Self = Self {
+        min: f32::INFINITY,
+        max: -f32::INFINITY,
+    };
+
+    pub const UNIVERSE: Self = Self {
+        min: -f32::INFINITY,
+        max: f32::INFINITY,
+    };
+
     pub fn new(min: f32, max: f32) -> Self {
         Interval { min, max }
     }
 
+    /// Smallest interval enclosing both `a` and `b`
+    pub fn enclosing(a: &Interval, b: &Interval) -> Self {
+        Interval {
+            min: a.min.min(b.min),
+            max: a.max.max(b.max),
+        }
+    }
+
+    pub fn size(&self) -> f32 {
+        self.max - self.min
+    }
+
     pub fn surrounds(&self, x: f32) -> bool {
         self.min < x && x < self.max
     }
@@ -21,6 +44,11 @@ impl Interval {
             x
         }
     }
+
+    pub fn expand(&self, delta: f32) -> Self {
+        let padding = delta / 2.0;
+        Interval::new(self.min - padding, self.max + padding)
+    }
 }
 
 impl Default for Interval {
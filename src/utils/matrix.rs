@@ -0,0 +1,230 @@
+use std::ops::Mul;
+
+use super::vec3::{Point3, Vec3};
+
+/// Affine transformation stored as a row-major 4x4 matrix acting on column vectors
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mat4 {
+    m: [[f32; 4]; 4],
+}
+
+impl Mat4 {
+    pub const IDENTITY: Self = Self {
+        m: [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ],
+    };
+
+    pub fn new(rows: [[f32; 4]; 4]) -> Self {
+        Self { m: rows }
+    }
+
+    pub fn translate(offset: &Vec3) -> Self {
+        Self::new([
+            [1.0, 0.0, 0.0, offset.x()],
+            [0.0, 1.0, 0.0, offset.y()],
+            [0.0, 0.0, 1.0, offset.z()],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Rotation by `degrees` about `axis` through the origin, counter-clockwise when looking
+    /// down the axis towards the origin
+    pub fn rotate(axis: &Vec3, degrees: f32) -> Self {
+        let a = axis.unit_vector();
+        let (x, y, z) = (a.x(), a.y(), a.z());
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let t = 1.0 - cos;
+
+        Self::new([
+            [
+                t * x * x + cos,
+                t * x * y - sin * z,
+                t * x * z + sin * y,
+                0.0,
+            ],
+            [
+                t * x * y + sin * z,
+                t * y * y + cos,
+                t * y * z - sin * x,
+                0.0,
+            ],
+            [
+                t * x * z - sin * y,
+                t * y * z + sin * x,
+                t * z * z + cos,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn scale(factors: &Vec3) -> Self {
+        Self::new([
+            [factors.x(), 0.0, 0.0, 0.0],
+            [0.0, factors.y(), 0.0, 0.0],
+            [0.0, 0.0, factors.z(), 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// True when the bottom row is 0 0 0 1, so points never need a perspective divide
+    pub fn is_affine(&self) -> bool {
+        self.m[3] == [0.0, 0.0, 0.0, 1.0]
+    }
+
+    /// Gauss-Jordan elimination in double precision, None for singular matrices
+    pub fn inverse(&self) -> Option<Self> {
+        let mut a = self.m.map(|row| row.map(f64::from));
+        let mut inv = Self::IDENTITY.m.map(|row| row.map(f64::from));
+
+        for col in 0..4 {
+            let pivot = (col..4).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale = 1.0 / a[col][col];
+            for j in 0..4 {
+                a[col][j] *= scale;
+                inv[col][j] *= scale;
+            }
+            for row in 0..4 {
+                if row != col {
+                    let factor = a[row][col];
+                    for j in 0..4 {
+                        a[row][j] -= factor * a[col][j];
+                        inv[row][j] -= factor * inv[col][j];
+                    }
+                }
+            }
+        }
+
+        Some(Self::new(inv.map(|row| row.map(|x| x as f32))))
+    }
+
+    /// Determinant of the upper 3x3 block, how much the matrix scales volumes
+    pub fn linear_determinant(&self) -> f32 {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    pub fn transform_point(&self, p: &Point3) -> Point3 {
+        let m = &self.m;
+        Point3::new(
+            m[0][0] * p.x() + m[0][1] * p.y() + m[0][2] * p.z() + m[0][3],
+            m[1][0] * p.x() + m[1][1] * p.y() + m[1][2] * p.z() + m[1][3],
+            m[2][0] * p.x() + m[2][1] * p.y() + m[2][2] * p.z() + m[2][3],
+        )
+    }
+
+    /// Applies only the linear part, directions are not moved by translations
+    pub fn transform_vector(&self, v: &Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x() + m[0][1] * v.y() + m[0][2] * v.z(),
+            m[1][0] * v.x() + m[1][1] * v.y() + m[1][2] * v.z(),
+            m[2][0] * v.x() + m[2][1] * v.y() + m[2][2] * v.z(),
+        )
+    }
+
+    /// Applies the transpose of the linear part, on an inverse matrix this carries normals
+    pub fn transpose_transform_vector(&self, v: &Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x() + m[1][0] * v.y() + m[2][0] * v.z(),
+            m[0][1] * v.x() + m[1][1] * v.y() + m[2][1] * v.z(),
+            m[0][2] * v.x() + m[1][2] * v.y() + m[2][2] * v.z(),
+        )
+    }
+}
+
+impl Default for Mat4 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// `a * b` applies `b` first, then `a`
+impl Mul for Mat4 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * rhs.m[k][j]).sum();
+            }
+        }
+        Self::new(m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: &Vec3, b: &Vec3) {
+        assert!((*a - *b).length() < 1e-4, "{a:?} != {b:?}");
+    }
+
+    fn placement() -> Mat4 {
+        Mat4::translate(&Vec3::new(1.0, -2.0, 3.0))
+            * Mat4::rotate(&Vec3::new(1.0, 2.0, 0.5), 37.0)
+            * Mat4::scale(&Vec3::new(2.0, 0.5, 3.0))
+    }
+
+    #[test]
+    fn inverse_round_trips() {
+        let m = placement();
+        let inv = m.inverse().unwrap();
+        for (i, row) in (m * inv).m.iter().enumerate() {
+            for (j, value) in row.iter().enumerate() {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((value - expected).abs() < 1e-5, "[{i}][{j}] = {value}");
+            }
+        }
+
+        let p = Point3::new(0.3, -4.0, 7.5);
+        assert_close(&inv.transform_point(&m.transform_point(&p)), &p);
+        assert!(Mat4::scale(&Vec3::new(1.0, 0.0, 1.0)).inverse().is_none());
+    }
+
+    #[test]
+    fn products_apply_the_right_operand_first() {
+        let translate = Mat4::translate(&Vec3::new(1.0, 0.0, 0.0));
+        let scale = Mat4::scale(&Vec3::new(2.0, 2.0, 2.0));
+        let p = Point3::new(1.0, 1.0, 1.0);
+
+        assert_close(
+            &(translate * scale).transform_point(&p),
+            &Point3::new(3.0, 2.0, 2.0),
+        );
+        assert_close(
+            &(scale * translate).transform_point(&p),
+            &Point3::new(4.0, 2.0, 2.0),
+        );
+        assert_close(
+            &(translate * scale).transform_vector(&p),
+            &Vec3::new(2.0, 2.0, 2.0),
+        );
+    }
+
+    #[test]
+    fn rotation_keeps_lengths_and_determinant() {
+        let r = Mat4::rotate(&Vec3::new(0.0, 0.0, 1.0), 90.0);
+        assert_close(
+            &r.transform_vector(&Vec3::new(1.0, 0.0, 0.0)),
+            &Vec3::new(0.0, 1.0, 0.0),
+        );
+        assert!((r.linear_determinant() - 1.0).abs() < 1e-5);
+        assert!((placement().linear_determinant() - 3.0).abs() < 1e-4);
+    }
+}
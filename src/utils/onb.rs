@@ -0,0 +1,79 @@
+use super::vec3::Vec3;
+
+/// Orthonormal basis with `w` along a given direction
+#[derive(Clone)]
+pub struct Onb {
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+}
+
+impl Onb {
+    pub fn new(n: &Vec3) -> Self {
+        let w = n.unit_vector();
+        // Any axis that is not nearly parallel to w works as a helper
+        let a = if w.x().abs() > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let v = w.cross(&a).unit_vector();
+        let u = w.cross(&v);
+
+        Self { u, v, w }
+    }
+
+    /// Basis with `w` along `n` and `u` along the part of `tangent` across it, so anisotropic
+    /// surfaces stretch along the same direction as their texture coordinates. Any `u` does when
+    /// the tangent is zero or parallel to `n`
+    pub fn with_tangent(n: &Vec3, tangent: &Vec3) -> Self {
+        let w = n.unit_vector();
+        let u = *tangent - w.scale(w.dot(tangent));
+        if u.length() <= 1e-4 * tangent.length() {
+            return Self::new(n);
+        }
+        let u = u.unit_vector();
+        let v = w.cross(&u);
+
+        Self { u, v, w }
+    }
+
+    pub fn w(&self) -> Vec3 {
+        self.w
+    }
+
+    /// Converts a world space vector to coordinates in this basis
+    pub fn to_local(&self, a: &Vec3) -> Vec3 {
+        Vec3::new(a.dot(&self.u), a.dot(&self.v), a.dot(&self.w))
+    }
+
+    /// Converts coordinates in this basis to world space
+    pub fn transform(&self, a: &Vec3) -> Vec3 {
+        self.u.scale(a.x()) + self.v.scale(a.y()) + self.w.scale(a.z())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tangent_frames_follow_the_tangent() {
+        let n = Vec3::new(0.0, 0.0, 2.0);
+        let frame = Onb::with_tangent(&n, &Vec3::new(3.0, 0.0, 1.0));
+        let u = frame.transform(&Vec3::new(1.0, 0.0, 0.0));
+        let v = frame.transform(&Vec3::new(0.0, 1.0, 0.0));
+        assert!((u - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-6, "{u:?}");
+        assert!((v - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-6, "{v:?}");
+
+        // Without a usable tangent any orthonormal frame around n will do
+        for tangent in [Vec3::default(), Vec3::new(0.0, 0.0, 5.0)] {
+            let frame = Onb::with_tangent(&n, &tangent);
+            let u = frame.transform(&Vec3::new(1.0, 0.0, 0.0));
+            assert!(
+                (u.length() - 1.0).abs() < 1e-6 && u.dot(&n).abs() < 1e-6,
+                "{u:?}"
+            );
+        }
+    }
+}
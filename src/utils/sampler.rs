@@ -0,0 +1,34 @@
+use rand::Rng;
+use rand_pcg::Pcg32;
+
+/// Seeded random number stream
+///
+/// Every pixel draws from its own stream, so a render is reproducible for a given seed no matter
+/// how scanlines are distributed across threads.
+pub struct Sampler {
+    rng: Pcg32,
+}
+
+impl Sampler {
+    pub fn new(seed: u64, stream: u64) -> Self {
+        // Scramble the state as well, streams sharing a starting state are correlated
+        Self {
+            rng: Pcg32::new(splitmix64(seed ^ splitmix64(stream)), stream),
+        }
+    }
+
+    pub fn random_float(&mut self) -> f32 {
+        self.rng.gen()
+    }
+
+    pub fn random_float_range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.random_float()
+    }
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
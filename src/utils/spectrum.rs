@@ -0,0 +1,190 @@
+use crate::{utils::sampler::Sampler, Colour};
+
+/// Wavelengths covered by the colour matching functions, in nanometres
+pub const LAMBDA_MIN: f32 = 360.0;
+pub const LAMBDA_MAX: f32 = 830.0;
+
+// Gaussian with a different width on either side of its peak
+fn piecewise_gaussian(lambda: f32, mu: f32, sigma_low: f32, sigma_high: f32) -> f32 {
+    let sigma = if lambda < mu { sigma_low } else { sigma_high };
+    let t = (lambda - mu) / sigma;
+    (-0.5 * t * t).exp()
+}
+
+/// CIE 1931 2° colour matching functions at `lambda` nanometres, using the multi-lobe fit of
+/// Wyman, Sloan and Shirley, "Simple Analytic Approximations to the CIE XYZ Color Matching
+/// Functions" (2013)
+pub fn cie_xyz(lambda: f32) -> [f32; 3] {
+    let x = 1.056 * piecewise_gaussian(lambda, 599.8, 37.9, 31.0)
+        + 0.362 * piecewise_gaussian(lambda, 442.0, 16.0, 26.7)
+        - 0.065 * piecewise_gaussian(lambda, 501.1, 20.4, 26.2);
+    let y = 0.821 * piecewise_gaussian(lambda, 568.8, 46.9, 40.5)
+        + 0.286 * piecewise_gaussian(lambda, 530.9, 16.3, 31.1);
+    let z = 1.217 * piecewise_gaussian(lambda, 437.0, 11.8, 36.0)
+        + 0.681 * piecewise_gaussian(lambda, 459.0, 26.0, 13.8);
+    [x, y, z]
+}
+
+/// Linear sRGB with D65 white from CIE XYZ
+pub fn xyz_to_srgb(xyz: [f32; 3]) -> Colour {
+    let [x, y, z] = xyz;
+    Colour::new(
+        3.240_454_2 * x - 1.537_138_5 * y - 0.498_531_4 * z,
+        -0.969_266 * x + 1.876_010_8 * y + 0.041_556 * z,
+        0.055_643_4 * x - 0.204_025_9 * y + 1.057_225_2 * z,
+    )
+}
+
+/// Spectral radiance of a black body at `kelvin`, `lambda` in nanometres
+pub fn planck(lambda: f32, kelvin: f32) -> f32 {
+    const C: f64 = 299_792_458.0;
+    const H: f64 = 6.626_070_15e-34;
+    const K_B: f64 = 1.380_649e-23;
+
+    if kelvin <= 0.0 {
+        return 0.0;
+    }
+    // Doubles, the terms overflow single precision long before the result does
+    let l = lambda as f64 * 1e-9;
+    let radiance =
+        2.0 * H * C * C / (l.powi(5) * ((H * C / (l * K_B * kelvin as f64)).exp() - 1.0));
+    radiance as f32
+}
+
+/// Linear sRGB colour of a black body at `kelvin`, with its spectrum scaled so the peak is 1
+///
+/// Hot bodies come out near white with a luminance close to 1, while cooler ones peak in the
+/// infrared and fade to a dim red, which is what makes fire look like fire.
+pub fn blackbody(kelvin: f32) -> Colour {
+    if kelvin <= 0.0 {
+        return Colour::default();
+    }
+
+    // Wien's displacement law
+    let peak = planck(2.897_772e6 / kelvin, kelvin);
+    let step = 5.0;
+    let mut xyz = [0.0; 3];
+    let mut y_integral = 0.0;
+    let mut lambda = LAMBDA_MIN;
+    while lambda <= LAMBDA_MAX {
+        let cmf = cie_xyz(lambda);
+        let radiance = planck(lambda, kelvin) / peak;
+        for (sum, weight) in xyz.iter_mut().zip(cmf) {
+            *sum += radiance * weight * step;
+        }
+        y_integral += cmf[1] * step;
+        lambda += step;
+    }
+
+    let rgb = xyz_to_srgb(xyz.map(|c| c / y_integral));
+    // Deep reds fall just outside the sRGB gamut
+    Colour::new(rgb.x().max(0.0), rgb.y().max(0.0), rgb.z().max(0.0))
+}
+
+/// Wavelengths splitting the upsampled spectra into blue, green and red boxes, chosen so
+/// colours come back within a few percent after a trip through the colour matching functions
+const BLUE_GREEN: f32 = 492.0;
+const GREEN_RED: f32 = 590.0;
+
+/// Value at `lambda` of the reflectance or radiance spectrum standing in for a linear sRGB
+/// colour, following the box basis of Smits, "An RGB-to-Spectrum Conversion for
+/// Reflectances" (1999). The conversion is linear, so scaled and summed colours map to scaled
+/// and summed spectra, and white maps to a flat spectrum of 1
+pub fn upsample(colour: &Colour, lambda: f32) -> f32 {
+    if lambda < BLUE_GREEN {
+        colour.z()
+    } else if lambda < GREEN_RED {
+        colour.y()
+    } else {
+        colour.x()
+    }
+}
+
+/// The wavelengths one camera path carries, a randomly placed hero wavelength and two more
+/// spread evenly across the visible range after it, wrapping around at the end
+///
+/// Spectral quantities along the path are stored in a `Colour` with one channel per
+/// wavelength, the hero in `x`, so the path tracer's arithmetic works unchanged.
+#[derive(Clone, Copy)]
+pub struct Wavelengths {
+    lambda: [f32; 3],
+}
+
+impl Wavelengths {
+    pub fn new(hero: f32) -> Self {
+        let range = LAMBDA_MAX - LAMBDA_MIN;
+        Self {
+            lambda: std::array::from_fn(|i| {
+                LAMBDA_MIN + (hero - LAMBDA_MIN + i as f32 * range / 3.0).rem_euclid(range)
+            }),
+        }
+    }
+
+    pub fn sample(sampler: &mut Sampler) -> Self {
+        Self::new(sampler.random_float_range(LAMBDA_MIN, LAMBDA_MAX))
+    }
+
+    pub fn hero(&self) -> f32 {
+        self.lambda[0]
+    }
+
+    /// All three wavelengths, hero first
+    pub fn lambdas(&self) -> [f32; 3] {
+        self.lambda
+    }
+
+    /// Spectral samples of a linear sRGB colour at these wavelengths
+    pub fn upsample(&self, colour: &Colour) -> Colour {
+        let [a, b, c] = self.lambda.map(|lambda| upsample(colour, lambda));
+        Colour::new(a, b, c)
+    }
+}
+
+/// Turns spectral radiance samples into linear sRGB by way of CIE XYZ
+///
+/// An equal energy spectrum comes out white rather than the faint pink it is next to D65, so
+/// scenes look the same in spectral and RGB rendering apart from dispersion.
+pub struct SpectralFilm {
+    /// Integral of the y colour matching function, which makes a flat spectrum of 1 have Y = 1
+    y_integral: f32,
+    white_balance: Colour,
+}
+
+impl Default for SpectralFilm {
+    fn default() -> Self {
+        let step = 1.0;
+        let mut xyz = [0.0; 3];
+        let mut lambda = LAMBDA_MIN;
+        while lambda <= LAMBDA_MAX {
+            for (sum, weight) in xyz.iter_mut().zip(cie_xyz(lambda)) {
+                *sum += weight * step;
+            }
+            lambda += step;
+        }
+
+        let y_integral = xyz[1];
+        let white = xyz_to_srgb(xyz.map(|c| c / y_integral));
+        Self {
+            y_integral,
+            white_balance: Colour::new(1.0 / white.x(), 1.0 / white.y(), 1.0 / white.z()),
+        }
+    }
+}
+
+impl SpectralFilm {
+    /// Linear sRGB seen at `lambda`, scaled so a flat spectrum of 1 averaged over wavelengths
+    /// drawn uniformly from the visible range comes out as white
+    pub fn response(&self, lambda: f32) -> Colour {
+        let scale = (LAMBDA_MAX - LAMBDA_MIN) / self.y_integral;
+        xyz_to_srgb(cie_xyz(lambda).map(|c| c * scale)) * self.white_balance
+    }
+
+    /// Linear sRGB estimate from `radiance` sampled at `wavelengths`
+    pub fn to_rgb(&self, wavelengths: &Wavelengths, radiance: &Colour) -> Colour {
+        let [a, b, c] = wavelengths.lambda;
+        (self.response(a).scale(radiance.x())
+            + self.response(b).scale(radiance.y())
+            + self.response(c).scale(radiance.z()))
+        .scale(1.0 / 3.0)
+    }
+}
@@ -1,6 +1,6 @@
 use std::ops::{Add, Index, Mul, Sub};
 
-use super::{random_float, random_float_range};
+use super::sampler::Sampler;
 #[derive(Copy, Clone, Default, Debug)]
 pub struct Vec3 {
     x: f32,
@@ -53,29 +53,60 @@ impl Vec3 {
         self.scale(1.0 / self.length())
     }
 
-    pub fn random() -> Self {
-        Self::new(random_float(), random_float(), random_float())
+    pub fn random(sampler: &mut Sampler) -> Self {
+        Self::new(
+            sampler.random_float(),
+            sampler.random_float(),
+            sampler.random_float(),
+        )
     }
 
-    pub fn random_range(min: f32, max: f32) -> Self {
+    pub fn random_range(sampler: &mut Sampler, min: f32, max: f32) -> Self {
         Self::new(
-            random_float_range(min, max),
-            random_float_range(min, max),
-            random_float_range(min, max),
+            sampler.random_float_range(min, max),
+            sampler.random_float_range(min, max),
+            sampler.random_float_range(min, max),
         )
     }
 
-    fn random_in_unit_sphere() -> Self {
+    fn random_in_unit_sphere(sampler: &mut Sampler) -> Self {
         loop {
-            let p = Self::random_range(-1.0, 1.0);
+            let p = Self::random_range(sampler, -1.0, 1.0);
             if (p.length() * p.length()) < 1.0 {
                 return p;
             }
         }
     }
 
-    pub fn random_unit_vector() -> Self {
-        Self::random_in_unit_sphere().unit_vector()
+    pub fn random_unit_vector(sampler: &mut Sampler) -> Self {
+        Self::random_in_unit_sphere(sampler).unit_vector()
+    }
+
+    /// Direction around +z with density cos(theta) / pi
+    pub fn random_cosine_direction(sampler: &mut Sampler) -> Self {
+        let r1 = sampler.random_float();
+        let r2 = sampler.random_float();
+
+        let phi = 2.0 * std::f32::consts::PI * r1;
+        let x = phi.cos() * r2.sqrt();
+        let y = phi.sin() * r2.sqrt();
+        let z = (1.0 - r2).sqrt();
+
+        Self::new(x, y, z)
+    }
+
+    /// Direction around +z uniformly inside the cone subtended by a sphere of `radius` whose
+    /// centre lies `distance_squared` away
+    pub fn random_to_sphere(sampler: &mut Sampler, radius: f32, distance_squared: f32) -> Self {
+        let r1 = sampler.random_float();
+        let r2 = sampler.random_float();
+        let cos_theta_max = (1.0 - radius * radius / distance_squared).max(0.0).sqrt();
+        let z = 1.0 + r2 * (cos_theta_max - 1.0);
+
+        let phi = 2.0 * std::f32::consts::PI * r1;
+        let sin_theta = (1.0 - z * z).max(0.0).sqrt();
+
+        Self::new(phi.cos() * sin_theta, phi.sin() * sin_theta, z)
     }
 
     pub fn near_zero(&self) -> bool {
@@ -99,11 +130,11 @@ impl Vec3 {
         r_out_perp + r_out_parallel
     }
 
-    pub fn random_in_unit_disk() -> Self {
+    pub fn random_in_unit_disk(sampler: &mut Sampler) -> Self {
         loop {
             let p = Vec3::new(
-                random_float_range(-1.0, 1.0),
-                random_float_range(-1.0, 1.0),
+                sampler.random_float_range(-1.0, 1.0),
+                sampler.random_float_range(-1.0, 1.0),
                 0.0,
             );
 
@@ -114,6 +145,12 @@ impl Vec3 {
     }
 }
 
+impl From<[f32; 3]> for Vec3 {
+    fn from(v: [f32; 3]) -> Self {
+        Self::new(v[0], v[1], v[2])
+    }
+}
+
 impl Index<usize> for Vec3 {
     type Output = f32;
 